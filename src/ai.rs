@@ -0,0 +1,557 @@
+use hexgame::Coords;
+use hexgame_ai::MctsHexGame;
+use rand::prelude::SmallRng;
+
+use crate::{
+    board::{self, count_stones, splitmix64, to_xy, GameMove, MAX_BOARD_SIZE},
+    search::{self, Exploration, Limits, RootChild},
+};
+
+/// Settings that control how strong (and how slow) the MCTS opponent is.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "persistence", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "persistence", serde(default))]
+pub struct AiConfig {
+    pub iterations: u32,
+    pub exploration_parameter: f32,
+    /// The exploration parameter at the end of a search. If set, the exploration parameter moves
+    /// from `exploration_parameter` at the first iteration towards this during each search, see
+    /// [`AiConfig::exploration`].
+    pub late_exploration_parameter: Option<f32>,
+    /// Upper bound for the number of nodes in the search tree, to keep memory in check.
+    ///
+    /// It is enforced by running fewer iterations, see [`AiConfig::iteration_budget`].
+    pub max_nodes: u32,
+    pub personality: Personality,
+    pub budget: SearchBudget,
+}
+
+impl Default for AiConfig {
+    fn default() -> Self {
+        Self {
+            iterations: 10_000,
+            exploration_parameter: 0.5,
+            late_exploration_parameter: None,
+            max_nodes: 5_000_000,
+            personality: Personality::Balanced,
+            budget: SearchBudget::Iterations,
+        }
+    }
+}
+
+/// What limits the length of a search.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "persistence", derive(serde::Deserialize, serde::Serialize))]
+pub enum SearchBudget {
+    /// Runs [`AiConfig::iterations`] iterations.
+    Iterations,
+    /// Runs as many iterations as fit into the given number of seconds, see [`search_limits`].
+    Time(f32),
+}
+
+impl AiConfig {
+    /// How the exploration parameter changes during a search.
+    pub(crate) fn exploration(&self) -> Exploration {
+        Exploration {
+            start: self.exploration_parameter,
+            end: self
+                .late_exploration_parameter
+                .unwrap_or(self.exploration_parameter),
+        }
+    }
+
+    /// The number of iterations a search on `game` may run without exceeding `max_nodes`.
+    ///
+    /// With full expansion, every iteration adds at most one node per empty cell. At least one
+    /// iteration is always run so the search can return a move.
+    pub fn iteration_budget(&self, game: &MctsHexGame) -> u32 {
+        self.iterations.min(self.node_limit(game))
+    }
+
+    /// The most iterations that keep the tree within `max_nodes`, but at least one.
+    fn node_limit(&self, game: &MctsHexGame) -> u32 {
+        let board = &game.game.board;
+        let empty_cells = board.size() as u32 * board.size() as u32 - count_stones(board) as u32;
+        (self.max_nodes / empty_cells.max(1)).max(1)
+    }
+}
+
+/// The style the AI plays in.
+///
+/// A personality biases the search towards the moves it prefers: the selection tries them first, a
+/// bias that fades as a move's visits grow, and the AI's own moves in the playouts favor them. The
+/// AI still plays the most visited move, so a move it prefers has to hold up in the search.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "persistence", derive(serde::Deserialize, serde::Serialize))]
+pub enum Personality {
+    /// Searches without a bias.
+    Balanced,
+    /// Prefers moves close to the center of the board.
+    Aggressive,
+    /// Prefers moves next to the opponent's stones, to block them.
+    Defensive,
+}
+
+impl Personality {
+    pub fn name(self) -> &'static str {
+        match self {
+            Personality::Balanced => "Balanced",
+            Personality::Aggressive => "Aggressive",
+            Personality::Defensive => "Defensive",
+        }
+    }
+
+    /// How well `action` suits this personality in `game`, between `0.0` and `1.0`.
+    pub(crate) fn preference(self, game: &MctsHexGame, action: Coords) -> f32 {
+        let board = &game.game.board;
+        let size = board.size();
+        match self {
+            Personality::Balanced => 0.0,
+            Personality::Aggressive => {
+                let max_distance = (size as f32 - 1.0).max(1.0);
+                1.0 - board::distance_from_center(action, size) / max_distance
+            }
+            Personality::Defensive => {
+                let opponent = Some(board::opponent(game.game.current_player));
+                let blocking = board::neighbors(action, size)
+                    .filter(|&neighbor| board.get_color(neighbor) == opponent)
+                    .count();
+                blocking as f32 / 6.0
+            }
+        }
+    }
+}
+
+/// Who makes the moves of one color.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "persistence", derive(serde::Deserialize, serde::Serialize))]
+pub enum Controller {
+    Human,
+    /// The AI, searching with the given settings.
+    Ai(AiConfig),
+}
+
+impl Controller {
+    pub fn name(self) -> &'static str {
+        match self {
+            Controller::Human => "Human",
+            Controller::Ai(_) => "AI",
+        }
+    }
+}
+
+/// Everything besides the seed and the moves that decides how a game against the AI goes, see
+/// [`game_id`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GameSettings {
+    pub size: u8,
+    pub controllers: [Controller; 2],
+    pub opponent: Opponent,
+    /// See [`should_resign`].
+    pub resign_threshold: f32,
+    /// Whether the second player may swap, which the AI considers in its reply, see [`reply`].
+    pub swap_rule: bool,
+    /// Whether the AI ponders, see [`ponder`]. Pondering draws random numbers of its own.
+    pub ponder: bool,
+}
+
+/// A short code that identifies a game played in deterministic mode, for bug reports.
+///
+/// Playing `moves` again with the same settings and seed reproduces the AI's moves, as long as no
+/// move was undone in between and no AI searches for a time budget. Any difference in them changes
+/// the ID, barring hash collisions.
+pub fn game_id(settings: &GameSettings, seed: u64, moves: &[GameMove]) -> String {
+    let mut words = vec![
+        u64::from(settings.size),
+        settings.opponent as u64,
+        u64::from(settings.resign_threshold.to_bits()),
+        settings.swap_rule as u64,
+        settings.ponder as u64,
+        seed,
+        moves.len() as u64,
+    ];
+    for controller in settings.controllers {
+        match controller {
+            Controller::Human => words.push(0),
+            Controller::Ai(config) => words.extend([
+                1,
+                u64::from(config.iterations),
+                u64::from(config.exploration_parameter.to_bits()),
+                config
+                    .late_exploration_parameter
+                    .map_or(u64::MAX, |late| u64::from(late.to_bits())),
+                u64::from(config.max_nodes),
+                config.personality as u64,
+                match config.budget {
+                    SearchBudget::Iterations => u64::MAX,
+                    SearchBudget::Time(seconds) => u64::from(seconds.to_bits()),
+                },
+            ]),
+        }
+    }
+    words.extend(moves.iter().map(|&game_move| match game_move {
+        GameMove::Stone(coords) => {
+            let (x, y) = to_xy(coords);
+            u64::from(x) * u64::from(MAX_BOARD_SIZE) + u64::from(y)
+        }
+        // Beyond every cell.
+        GameMove::Swap => u64::from(MAX_BOARD_SIZE) * u64::from(MAX_BOARD_SIZE),
+    }));
+
+    let hash = words
+        .into_iter()
+        .fold(0, |hash, word| splitmix64(hash ^ word));
+    format!(
+        "{:04x}-{:04x}-{:04x}",
+        hash >> 48,
+        (hash >> 32) & 0xffff,
+        (hash >> 16) & 0xffff
+    )
+}
+
+/// How the AI chooses its moves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "persistence", derive(serde::Deserialize, serde::Serialize))]
+pub enum Opponent {
+    /// The regular MCTS search.
+    Mcts,
+    /// Answers every move by mirroring it across the board's center, falling back to MCTS when the
+    /// mirrored cell is taken. Useful for demonstrating why mirroring loses in Hex.
+    Mirror,
+}
+
+impl Opponent {
+    pub fn name(self) -> &'static str {
+        match self {
+            Opponent::Mcts => "MCTS",
+            Opponent::Mirror => "Mirror",
+        }
+    }
+}
+
+/// Reflects `coords` through the center of a board with `size` cells per side.
+pub fn mirror_move(coords: Coords, size: u8) -> Coords {
+    let (x, y) = to_xy(coords);
+    Coords::new(size - 1 - x, size - 1 - y)
+}
+
+/// When a search with `config` on `game` stops.
+///
+/// A time budget stops the search on the clock, after the iteration that is running when the time
+/// is up. A budget too small for even one iteration still runs one, so a move is always found, it
+/// just takes longer than asked. `max_nodes` applies either way.
+///
+/// The browser has no clock to measure with, there a time budget falls back to
+/// [`AiConfig::iterations`].
+fn search_limits(game: &MctsHexGame, config: &AiConfig) -> Limits {
+    match config.budget {
+        SearchBudget::Iterations => Limits {
+            iterations: config.iteration_budget(game),
+            #[cfg(not(target_arch = "wasm32"))]
+            time: None,
+        },
+        #[cfg(not(target_arch = "wasm32"))]
+        SearchBudget::Time(seconds) => Limits {
+            iterations: config.node_limit(game),
+            time: Some(std::time::Duration::from_secs_f32(seconds.max(0.0))),
+        },
+        #[cfg(target_arch = "wasm32")]
+        SearchBudget::Time(_) => Limits {
+            iterations: config.iteration_budget(game),
+        },
+    }
+}
+
+/// How many of the most visited moves a [`Suggestion`] keeps.
+pub const CANDIDATE_COUNT: usize = 5;
+
+/// A move the search considered at the root, with the statistics it gathered for it.
+#[derive(Clone, Copy, Debug)]
+pub struct Candidate {
+    pub action: Coords,
+    pub visits: u32,
+    /// Estimated probability that the player to move wins after playing `action`.
+    pub win_probability: f32,
+}
+
+impl Candidate {
+    fn from_child(child: &RootChild) -> Self {
+        Self {
+            action: child.action,
+            visits: child.visits,
+            win_probability: if child.visits == 0 {
+                0.5
+            } else {
+                child.wins as f32 / child.visits as f32
+            },
+        }
+    }
+}
+
+/// The outcome of a search: the chosen move and how the AI rates it.
+#[derive(Clone, Debug)]
+pub struct Suggestion {
+    pub action: Coords,
+    /// Estimated probability that the player to move wins after playing `action`.
+    ///
+    /// This is the win ratio of the chosen root child, so it is only as reliable as the search was long.
+    pub win_probability: f32,
+    /// The most visited moves, most visited first. Starts with `action` unless the move won right
+    /// away without a search, then it is empty.
+    pub candidates: Vec<Candidate>,
+    /// The fraction of the root's visits that went to `action`.
+    pub visit_share: f32,
+}
+
+/// Visit share above which a move counts as forced, see [`Suggestion::is_forced`].
+const FORCED_VISIT_SHARE: f32 = 0.9;
+
+impl Suggestion {
+    /// Whether the search spent nearly all of its time on `action`, i.e. the alternatives were
+    /// quickly found to be much worse.
+    pub fn is_forced(&self) -> bool {
+        self.visit_share >= FORCED_VISIT_SHARE
+    }
+}
+
+/// Runs a full search on `game` and returns the move the AI would play for the current player.
+///
+/// Returns `None` if the search did not produce a move, e.g. because the game is already over.
+///
+/// A move that wins right away is played without searching.
+pub fn suggest(game: &MctsHexGame, config: &AiConfig, rng: &mut SmallRng) -> Option<Suggestion> {
+    if let Some(action) = board::immediate_winning_move(game, game.game.current_player) {
+        let suggestion = Suggestion {
+            action,
+            win_probability: 1.0,
+            candidates: Vec::new(),
+            visit_share: 1.0,
+        };
+        #[cfg(feature = "ai-logging")]
+        log_search(game, 0, &suggestion, None);
+        return Some(suggestion);
+    }
+
+    #[cfg(all(feature = "ai-logging", not(target_arch = "wasm32")))]
+    let started = std::time::Instant::now();
+    let result = search::search(
+        game,
+        config.exploration(),
+        config.personality,
+        search_limits(game, config),
+        rng,
+    )?;
+    #[cfg(feature = "ai-logging")]
+    let iterations = result.iterations;
+    // The most visited move, its estimate is the most reliable.
+    let mut candidates: Vec<_> = result.children.iter().map(Candidate::from_child).collect();
+    let action = candidates.first()?.action;
+    let chosen = candidates
+        .iter()
+        .find(|candidate| candidate.action == action);
+    let win_probability = chosen.map_or(0.5, |candidate| candidate.win_probability);
+    let total_visits: u32 = candidates.iter().map(|candidate| candidate.visits).sum();
+    let visit_share = match chosen {
+        Some(candidate) if total_visits > 0 => candidate.visits as f32 / total_visits as f32,
+        _ => 0.0,
+    };
+    candidates.truncate(CANDIDATE_COUNT);
+
+    let suggestion = Suggestion {
+        action,
+        win_probability,
+        candidates,
+        visit_share,
+    };
+    #[cfg(feature = "ai-logging")]
+    {
+        // `Instant` is not available in the browser.
+        #[cfg(not(target_arch = "wasm32"))]
+        let elapsed = Some(started.elapsed());
+        #[cfg(target_arch = "wasm32")]
+        let elapsed = None;
+        log_search(game, iterations, &suggestion, elapsed);
+    }
+    Some(suggestion)
+}
+
+/// Logs the outcome of a search on `game` as `key=value` pairs, for diagnosing the AI's strength.
+#[cfg(feature = "ai-logging")]
+fn log_search(
+    game: &MctsHexGame,
+    iterations: u32,
+    suggestion: &Suggestion,
+    elapsed: Option<std::time::Duration>,
+) {
+    use crate::notation::{format_coord, CoordinateSystem};
+
+    let candidates: Vec<_> = suggestion
+        .candidates
+        .iter()
+        .map(|candidate| {
+            format!(
+                "{}:{}:{:.3}",
+                format_coord(candidate.action, CoordinateSystem::Letters),
+                candidate.visits,
+                candidate.win_probability
+            )
+        })
+        .collect();
+    log::info!(
+        "search hash={:016x} iterations={} move={} win_probability={:.3} visit_share={:.3} candidates={} elapsed_ms={}",
+        board::position_hash(&game.game.board),
+        iterations,
+        format_coord(suggestion.action, CoordinateSystem::Letters),
+        suggestion.win_probability,
+        suggestion.visit_share,
+        candidates.join(","),
+        elapsed.map_or_else(|| "unknown".to_owned(), |elapsed| elapsed.as_millis().to_string())
+    );
+}
+
+/// Searches ahead while the opponent thinks about `game`: predicts the opponent's move with one
+/// search and then searches the AI's reply to it. Returns the predicted move and the reply.
+///
+/// Every search builds a new tree, so the work is reused by playing the reply right
+/// away when the opponent plays the predicted move.
+pub fn ponder(
+    game: &MctsHexGame,
+    config: &AiConfig,
+    rng: &mut SmallRng,
+) -> Option<(Coords, Suggestion)> {
+    let predicted = best_move(game, config, rng)?;
+    let mut after = game.clone();
+    after.play(predicted).ok()?;
+    let reply = suggest(&after, config, rng)?;
+    Some((predicted, reply))
+}
+
+/// What the AI does on its turn.
+#[derive(Clone, Debug)]
+pub enum AiReply {
+    Move(Suggestion),
+    /// Take over the opponent's first stone under the swap rule, see [`board::swap_sides`].
+    Swap,
+}
+
+/// The AI's reply in `game`. If `swap_offered`, searches the position after a swap as well and
+/// swaps if that leaves the AI better off than its best move.
+///
+/// Returns `None` if the search found no move. Hex has no passes, so the AI then cannot continue.
+pub fn reply(
+    game: &MctsHexGame,
+    config: &AiConfig,
+    rng: &mut SmallRng,
+    swap_offered: bool,
+) -> Option<AiReply> {
+    let suggestion = suggest(game, config, rng)?;
+    if swap_offered {
+        // The opponent is to move after the swap, so their chances are the AI's losing chances.
+        if let Some(opponent) = evaluate(&board::swap_sides(game), config, rng) {
+            if 1.0 - opponent > suggestion.win_probability {
+                return Some(AiReply::Swap);
+            }
+        }
+    }
+    Some(AiReply::Move(suggestion))
+}
+
+/// Like [`suggest`], but only returns the move.
+pub fn best_move(game: &MctsHexGame, config: &AiConfig, rng: &mut SmallRng) -> Option<Coords> {
+    suggest(game, config, rng).map(|suggestion| suggestion.action)
+}
+
+/// Estimated probability that the player to move wins `game`, according to a search with `config`.
+pub fn evaluate(game: &MctsHexGame, config: &AiConfig, rng: &mut SmallRng) -> Option<f32> {
+    suggest(game, config, rng).map(|suggestion| suggestion.win_probability)
+}
+
+/// Whether the AI should give up instead of playing `suggestion`.
+///
+/// A `threshold` of `0.0` disables resigning.
+pub fn should_resign(suggestion: &Suggestion, threshold: f32) -> bool {
+    suggestion.win_probability < threshold
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::{board::test_game, headless};
+
+    #[test]
+    fn long_search_returns_legal_move() {
+        let config = AiConfig {
+            iterations: 50_000,
+            ..AiConfig::default()
+        };
+        let game = test_game(3, &[(1, 1)]);
+        let mut rng = SmallRng::seed_from_u64(1);
+
+        let action = best_move(&game, &config, &mut rng).expect("the search finds a move");
+        assert!(game.game.board.get_color(action).is_none());
+    }
+
+    #[test]
+    fn tiny_time_budget_still_returns_legal_move() {
+        let config = AiConfig {
+            budget: SearchBudget::Time(0.0),
+            ..AiConfig::default()
+        };
+        let game = test_game(5, &[(2, 2)]);
+        let mut rng = SmallRng::seed_from_u64(1);
+
+        let action = best_move(&game, &config, &mut rng).expect("the search finds a move");
+        assert!(game.game.board.get_color(action).is_none());
+    }
+
+    #[test]
+    fn seeded_ai_game_is_reproducible() {
+        let config = AiConfig {
+            iterations: 200,
+            ..AiConfig::default()
+        };
+        let play = || {
+            let mut rng = SmallRng::seed_from_u64(42);
+            headless::play_ai_vs_ai(4, &config, 0.0, &mut rng)
+        };
+
+        let first = play();
+        let second = play();
+        assert_eq!(first.moves, second.moves);
+        assert_eq!(first.winner, second.winner);
+    }
+
+    #[test]
+    fn game_id_changes_with_seed_and_moves() {
+        let settings = GameSettings {
+            size: 5,
+            controllers: [Controller::Human, Controller::Ai(AiConfig::default())],
+            opponent: Opponent::Mcts,
+            resign_threshold: 0.0,
+            swap_rule: false,
+            ponder: false,
+        };
+        let moves = [
+            GameMove::Stone(Coords::new(2, 2)),
+            GameMove::Stone(Coords::new(1, 3)),
+        ];
+        let id = game_id(&settings, 7, &moves);
+
+        assert_eq!(id, game_id(&settings, 7, &moves));
+        assert_ne!(id, game_id(&settings, 8, &moves));
+        let swapping = GameSettings {
+            swap_rule: true,
+            ..settings
+        };
+        assert_ne!(id, game_id(&swapping, 7, &moves));
+        assert_ne!(id, game_id(&settings, 7, &moves[..1]));
+        // The history records a swap on the mirrored first stone, which is the same cell here, so
+        // only the swap tells the games apart.
+        let stone = [moves[0], GameMove::Stone(Coords::new(2, 2))];
+        let swap = [moves[0], GameMove::Swap];
+        assert_ne!(game_id(&swapping, 7, &stone), game_id(&swapping, 7, &swap));
+        assert_eq!(id.len(), "0000-0000-0000".len());
+    }
+}
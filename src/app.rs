@@ -1,18 +1,40 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+#[cfg(not(target_arch = "wasm32"))]
+use std::{path::Path, time::Duration};
+
 use eframe::{
     egui::{
-        self, Color32, Pos2, Response, Sense, Shape, Slider, Stroke, Ui, Vec2, Visuals, Widget,
+        self, Align2, Color32, Painter, Pos2, Response, Sense, Shape, Slider, Stroke, TextStyle,
+        Ui, Vec2, Visuals, Widget,
     },
     epi,
 };
-use hexgame::{Color, Coords, Game, Status};
-use hexgame_ai::{HexNodeContent, MctsHexGame};
-use mcts::{
-    action_decision::SelectRobustChild, full_expansion::FullExpansion,
-    shuffled_playout::ShuffledPlayout, time_control::ConstIterationCount,
-    uct_selection::UctSelection, uct_update::UctUpdate, Game as _, Mcts, NodeContent,
-};
+use hexgame::{Board, Color, Coords, Status};
+use hexgame_ai::MctsHexGame;
+use mcts::Game as _;
 use rand::{prelude::SmallRng, SeedableRng};
 
+#[cfg(not(target_arch = "wasm32"))]
+use crate::oracle::{self, MAX_ORACLE_SIZE};
+use crate::{
+    ai::{
+        self, AiConfig, AiReply, Controller, GameSettings, Opponent, Personality, SearchBudget,
+        Suggestion,
+    },
+    board::{self, Axis, GameMove, MAX_BOARD_SIZE, MIN_BOARD_SIZE},
+    console::{self, Command},
+    drill::{self, Drill, Feedback},
+    geometry::{self, DEFAULT_CELL_GAP, HEX_CORNERS, HEX_SIZE},
+    headless,
+    history::{History, UndoLimit},
+    notation::{self, CoordinateSystem},
+    openings,
+    summary::{self, GameResult, GameSummary, MoveSummary},
+    svg::{self, SvgOptions},
+    theme::Theme,
+};
+
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 #[cfg_attr(feature = "persistence", derive(serde::Deserialize, serde::Serialize))]
 #[cfg_attr(feature = "persistence", serde(default))] // if we add new fields, give them default values when deserializing old state
@@ -20,237 +42,4370 @@ pub struct HexGameUi {
     #[cfg_attr(feature = "persistence", serde(skip))]
     game: MctsHexGame,
     configured_size: u8,
+    /// The name of the opening whose moves are played after a reset, if one exists for the board size.
+    opening: Option<String>,
+    /// See [`openings::openings`].
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    openings: HashMap<u8, Vec<(String, Vec<Coords>)>>,
+    /// The opening being practiced. The human plays both sides and only the opening's moves are
+    /// accepted until it is complete.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    drill: Option<Drill>,
+    /// The [`Drill::key`]s of every opening practiced to the end.
+    completed_drills: BTreeSet<String>,
+    analysis_mode: bool,
+    board_margin: f32,
+    /// Space between neighboring cells, as a fraction of the cell size.
+    cell_gap: f32,
+    /// The board is scaled to fit the window, but its cells never get smaller than this. Beyond
+    /// that, the board can be scrolled instead.
+    min_cell_size: f32,
+    /// Cells never get larger than this when a small board is scaled up.
+    max_cell_size: f32,
+    show_side_panel: bool,
+    /// The AI resigns once its estimated win probability drops below this value. `0.0` disables resigning.
+    ai_resign_threshold: f32,
+    opponent: Opponent,
+    /// Who plays each color, indexed by [`board::color_index`].
+    controllers: [Controller; 2],
+    /// While the AI plays both sides for the user to watch, which sides a human played before and
+    /// gets back afterwards, indexed by [`board::color_index`].
+    watching: Option<[bool; 2]>,
+    /// Raise the AI's iteration count by `difficulty_step` whenever the human wins, and lower it when
+    /// the human loses. An AI with a time budget gets [`DIFFICULTY_TIME_STEP`] more or less time
+    /// instead.
+    adaptive_difficulty: bool,
+    difficulty_step: u32,
+    /// Whether the current game's result has been applied to the difficulty already.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    difficulty_adjusted: bool,
+    /// Finished games, the most recent first.
+    recent_games: VecDeque<RecentGame>,
+    /// The game in progress when the app was closed, in the notation of [`notation::format_game`].
+    /// `game` itself cannot be persisted, so it is replayed from this on startup.
+    #[cfg(feature = "persistence")]
+    saved_game: Option<String>,
+    /// The final position of every recent game, for its thumbnail. `None` for games that can no
+    /// longer be replayed. Rebuilt whenever the list changes.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    recent_thumbnails: Vec<Option<Board>>,
+    /// When the current game started and ended, in seconds since the Unix epoch, for
+    /// [`HexGameUi::game_summary`].
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    game_started: Option<u64>,
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    game_finished: Option<u64>,
+    /// Whether the current game has been added to the recent games already.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    recent_game_recorded: bool,
+    /// Names shown for the players instead of their colors.
+    red_name: String,
+    blue_name: String,
+    /// Seed the AI from `seed` at the start of every game, so games can be reproduced.
+    deterministic: bool,
+    seed: u64,
+    /// Shared by all searches of a game, so consecutive AI moves do not start from the same state.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    rng: SmallRng,
+    /// For hints, blunder checks and the engine console, so using them does not change the AI's
+    /// moves, see [`ai::game_id`].
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    analysis_rng: SmallRng,
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    resigned: Option<Color>,
+    /// Mark every empty cell by whether it wins with perfect play. Only on tiny boards.
+    #[cfg(not(target_arch = "wasm32"))]
+    show_oracle: bool,
+    /// The oracle's verdict, see [`oracle::solve`], for the position with the given
+    /// [`board::position_key`].
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    oracle: Option<(u64, Vec<(Coords, bool)>)>,
+    /// The position the oracle is solving on a background thread, and where its verdict arrives.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    oracle_job: Option<(u64, Receiver<Vec<(Coords, bool)>>)>,
+    /// Search the AI's reply to the human's predicted move on a background thread while the human
+    /// thinks, see [`ai::ponder`].
+    #[cfg(not(target_arch = "wasm32"))]
+    ponder: bool,
+    /// The [`board::position_key`] of the position being pondered on. The predicted position's key
+    /// and the reply to it arrive through the receiver.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    ponder_job: Option<(u64, Receiver<(u64, Suggestion)>)>,
+    /// The move suggested by "Hint", with the hash of the position it was suggested for. Cleared
+    /// when the human moves or the game restarts, so it does not reappear on undo.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    hint: Option<(Coords, u64)>,
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    history: History,
+    undo_limit: UndoLimit,
+    /// Use [`default_ai_delay`] for the board size instead of `ai_delay`.
+    automatic_ai_delay: bool,
+    /// Seconds to wait after the human's move before the AI replies, so the reply can be followed.
+    ai_delay: f32,
+    /// Input time at which the pending AI reply is due.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    ai_move_due: Option<f64>,
+    /// Search as soon as it is the AI's turn and play forced moves right away. Other moves still wait
+    /// for the AI delay.
+    instant_forced_moves: bool,
+    /// Let the AI make the first move of a game by itself when it moves first.
+    auto_start_ai: bool,
+    /// The AI's search while it runs on a background thread, so the window stays responsive, with
+    /// the [`board::position_key`] of the position it searches. Its reply arrives through the
+    /// receiver.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    ai_search: Option<(u64, Receiver<Option<AiReply>>)>,
+    /// The [`board::position_key`] of the position the AI's search failed or found no move in. The
+    /// AI does not try that position again.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    ai_failed: Option<u64>,
+    /// Let the second player take over the first stone instead of answering it, which balances
+    /// the first player's advantage.
+    swap_rule: bool,
+    /// A move the AI has chosen early because of `instant_forced_moves`, with the input time at
+    /// which the AI delay has passed.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    held_ai_move: Option<(Coords, f64)>,
+    /// Seconds to flash the AI's chosen cell before its stone is placed. `0.0` places it right away.
+    ai_preview_duration: f32,
+    /// The move the AI has chosen but not played yet, with the input time at which it is played.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    ai_preview: Option<(Coords, f64)>,
+    /// Counts the games and positions the AI's pending moves may have been made for. Raised by
+    /// [`HexGameUi::cancel_ai_move`], so AI moves queued before are dropped.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    ai_generation: u64,
+    /// Moves requested during the current frame, see [`MoveEvent`].
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    move_events: Vec<MoveEvent>,
+    /// The cell of the last rejected click, and the input time of the click.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    rejected_click: Option<(Coords, f64)>,
+    /// Input time of the last move played by a click, see [`CLICK_DEBOUNCE`].
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    last_move_time: Option<f64>,
+    /// The cells of the human's and the AI's most recent moves, marked on the board.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    last_human_move: Option<Coords>,
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    last_ai_move: Option<Coords>,
+    /// The most recent MCTS search, for the analysis section.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    last_search: Option<Suggestion>,
+    /// Draw numbered markers on the cells of the last search's top candidates.
+    show_candidates: bool,
+    /// Draw the bridges of the side to move, as a teaching aid.
+    show_bridges: bool,
+    /// Label the columns along the top of the board and the rows down its side.
+    show_coordinates: bool,
+    theme: ThemePreference,
+    /// Draw the board with [`Theme::high_contrast`] instead of [`Theme::standard`].
+    high_contrast: bool,
+    /// Share of AI self-play games won by the first player, per board size. Kept across sessions
+    /// because estimating it takes a while on large boards.
+    first_move_advantage: BTreeMap<u8, f32>,
+    /// The running estimate of the first move advantage, by board size.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    advantage_job: Option<(u8, Receiver<Option<f32>>)>,
+    /// Evaluate the position before and after each human move and flag large drops as blunders.
+    detect_blunders: bool,
+    /// The running blunder checks, see [`HexGameUi::start_blunder_check`], by the move number and
+    /// coordinates of the checked move.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    blunder_checks: Vec<(usize, Coords, Receiver<Option<BlunderCheck>>)>,
+    coordinate_system: CoordinateSystem,
+    /// Animate the board. Turn off to reduce motion.
+    animations: bool,
+    /// How fast the highlight of the winning path flows from edge to edge, in cells per second.
+    victory_flow_speed: f32,
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    hover_highlight: Option<Vec2>,
+    /// Print internals like the position hash on top of the board.
+    show_debug_overlay: bool,
+    /// Place stones by pressing on the board, dragging to the cell and releasing there, instead of
+    /// only by clicking.
+    drag_to_place: bool,
+    off_board_click: OffBoardClick,
+    /// Longest time in seconds between repaints while waiting for the AI. `0.0` repaints
+    /// continuously.
+    idle_repaint_interval: f32,
+    /// Input time of the next repaint already asked for by [`HexGameUi::request_repaint_after`].
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    wake_up: Option<f64>,
+    /// While the size slider is in use, outline a board of the configured size over the current one.
+    show_size_overlay: bool,
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    size_slider_active: bool,
+    /// Label the cells with their coordinates in exported images.
+    export_coordinates: bool,
+    #[cfg(not(target_arch = "wasm32"))]
+    png_export_path: String,
+    /// Pixels per point of the on-screen board in exported PNG files.
+    #[cfg(not(target_arch = "wasm32"))]
+    png_export_scale: f32,
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    png_export_error: Option<String>,
+    /// The game text being edited in the export section, see [`Self::game_text`].
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    game_text_input: String,
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    game_text_error: Option<String>,
+    /// The line being typed into the engine console.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    console_input: String,
+    /// Commands entered into the engine console and their output, oldest first.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    console_output: Vec<String>,
+    /// The running search of [`HexGameUi::start_search`], with the [`board::position_key`] of the
+    /// searched position.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    search_job: Option<(SearchPurpose, u64, Receiver<Option<Suggestion>>)>,
+    /// A position shown next to the game, for comparing the two.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    comparison: Option<MctsHexGame>,
+    /// The number of retained moves of the position shown instead of the game, after a click on a
+    /// move in the history.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    rewind: Option<usize>,
+    /// Why the last "Play from here" failed.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    play_from_here_error: Option<String>,
+    /// Append every finished game to `game_log_path`.
+    #[cfg(not(target_arch = "wasm32"))]
+    log_games: bool,
+    #[cfg(not(target_arch = "wasm32"))]
+    game_log_path: String,
+    /// Whether the current game has been written to the log already.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    game_logged: bool,
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    game_log_error: Option<String>,
+    /// The game as last written to the page URL.
+    #[cfg(target_arch = "wasm32")]
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    shared_game: String,
 }
 
 impl Default for HexGameUi {
     fn default() -> Self {
+        let game = board::empty_game(DEFAULT_BOARD_SIZE);
+        let undo_limit = UndoLimit::Automatic;
         Self {
-            game: MctsHexGame::new(5, 0, 1),
-            configured_size: 5,
+            history: History::new(&game, undo_limit.max_moves(DEFAULT_BOARD_SIZE)),
+            undo_limit,
+            automatic_ai_delay: true,
+            ai_delay: default_ai_delay(DEFAULT_BOARD_SIZE),
+            ai_move_due: None,
+            instant_forced_moves: false,
+            auto_start_ai: true,
+            #[cfg(not(target_arch = "wasm32"))]
+            ai_search: None,
+            ai_failed: None,
+            swap_rule: false,
+            held_ai_move: None,
+            ai_preview_duration: 0.0,
+            ai_preview: None,
+            ai_generation: 0,
+            move_events: Vec::new(),
+            rejected_click: None,
+            last_move_time: None,
+            last_human_move: None,
+            last_ai_move: None,
+            last_search: None,
+            show_candidates: false,
+            show_bridges: false,
+            show_coordinates: true,
+            theme: ThemePreference::System,
+            high_contrast: false,
+            first_move_advantage: BTreeMap::new(),
+            advantage_job: None,
+            detect_blunders: false,
+            blunder_checks: Vec::new(),
+            coordinate_system: CoordinateSystem::Letters,
+            animations: true,
+            victory_flow_speed: 4.0,
+            hover_highlight: None,
+            show_debug_overlay: false,
+            drag_to_place: false,
+            off_board_click: OffBoardClick::Ignore,
+            idle_repaint_interval: 0.25,
+            #[cfg(not(target_arch = "wasm32"))]
+            wake_up: None,
+            show_size_overlay: true,
+            size_slider_active: false,
+            export_coordinates: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            png_export_path: "board.png".to_owned(),
+            #[cfg(not(target_arch = "wasm32"))]
+            png_export_scale: 2.0,
+            #[cfg(not(target_arch = "wasm32"))]
+            png_export_error: None,
+            game_text_input: String::new(),
+            game_text_error: None,
+            comparison: None,
+            rewind: None,
+            play_from_here_error: None,
+            console_input: String::new(),
+            console_output: Vec::new(),
+            search_job: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            log_games: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            game_log_path: "hex_games.log".to_owned(),
+            #[cfg(not(target_arch = "wasm32"))]
+            game_logged: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            game_log_error: None,
+            #[cfg(target_arch = "wasm32")]
+            shared_game: String::new(),
+            game,
+            configured_size: DEFAULT_BOARD_SIZE,
+            opening: None,
+            openings: openings::openings(),
+            drill: None,
+            completed_drills: BTreeSet::new(),
+            analysis_mode: false,
+            board_margin: 20.0,
+            cell_gap: DEFAULT_CELL_GAP,
+            min_cell_size: 24.0,
+            max_cell_size: 80.0,
+            show_side_panel: true,
+            ai_resign_threshold: 0.0,
+            opponent: Opponent::Mcts,
+            adaptive_difficulty: false,
+            difficulty_step: 2_000,
+            difficulty_adjusted: false,
+            recent_games: VecDeque::new(),
+            #[cfg(feature = "persistence")]
+            saved_game: None,
+            recent_thumbnails: Vec::new(),
+            game_started: unix_time(),
+            game_finished: None,
+            recent_game_recorded: false,
+            red_name: board::color_name(Color::Black).to_owned(),
+            blue_name: board::color_name(Color::White).to_owned(),
+            controllers: [Controller::Human, Controller::Ai(AiConfig::default())],
+            watching: None,
+            deterministic: false,
+            seed: 0,
+            rng: SmallRng::from_entropy(),
+            analysis_rng: SmallRng::from_entropy(),
+            resigned: None,
+            hint: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            show_oracle: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            oracle: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            oracle_job: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            ponder: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            ponder_job: None,
         }
     }
 }
 
-const HEX_SIZE: f32 = 40.0;
+impl HexGameUi {
+    /// Replaces the current game with an empty board of the given size, see [`board::empty_game`].
+    fn new_game(&mut self, size: u8) {
+        self.game = board::empty_game(size);
+        let size = self.game.game.board.size();
+        self.history = History::new(&self.game, self.undo_limit.max_moves(size));
+        self.resigned = None;
+        self.cancel_ai_move();
+        self.last_search = None;
+        self.forget_last_moves();
+        self.hint = None;
+        self.rewind = None;
+        self.drill = None;
+        self.difficulty_adjusted = false;
+        self.recent_game_recorded = false;
+        self.game_started = unix_time();
+        self.game_finished = None;
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.game_logged = false;
+            self.ponder_job = None;
+        }
+        self.reset_rng();
+    }
 
-const fn hex_coords() -> [Vec2; 6] {
-    [
-        Vec2::new(0.00000, 1.00000),
-        Vec2::new(0.86603, 0.50000),
-        Vec2::new(0.86603, -0.50000),
-        Vec2::new(0.00000, -1.00000),
-        Vec2::new(-0.86603, -0.50000),
-        Vec2::new(-0.86603, 0.50000),
-    ]
-}
+    /// Unmarks the last moves, once the board no longer shows the position they were played in.
+    fn forget_last_moves(&mut self) {
+        self.last_human_move = None;
+        self.last_ai_move = None;
+    }
 
-impl epi::App for HexGameUi {
-    fn name(&self) -> &str {
-        "eframe template"
+    /// The moves of the chosen opening on a board of the given size. Empty without an opening.
+    fn opening_moves(&self, size: u8) -> &[Coords] {
+        self.openings
+            .get(&size)
+            .and_then(|openings| {
+                openings
+                    .iter()
+                    .find(|(name, _)| Some(name) == self.opening.as_ref())
+            })
+            .map_or(&[][..], |(_, moves)| moves.as_slice())
     }
 
-    /// Called once before the first frame.
-    fn setup(
-        &mut self,
-        ctx: &egui::CtxRef,
-        _frame: &epi::Frame,
-        _storage: Option<&dyn epi::Storage>,
-    ) {
-        // Load previous app state (if any).
-        // Note that you must enable the `persistence` feature for this to work.
-        #[cfg(feature = "persistence")]
-        if let Some(storage) = _storage {
-            *self = epi::get_value(storage, epi::APP_KEY).unwrap_or_default()
+    /// Whether nothing has been played yet except for the chosen opening. A drill starts on an
+    /// empty board instead.
+    fn is_untouched(&self) -> bool {
+        let opening = if self.drill.is_some() {
+            &[][..]
+        } else {
+            self.opening_moves(self.game.game.board.size())
+        };
+        self.history
+            .all_moves()
+            .map(|m| m.coords)
+            .eq(opening.iter().copied())
+    }
+
+    /// Starts a new game at the configured size and plays the chosen opening, if any. A running
+    /// drill starts over instead.
+    fn reset_game(&mut self) {
+        if let Some(drill) = self.drill.take() {
+            self.start_drill(drill.size, drill.name);
+            return;
         }
+        self.new_game(self.configured_size);
+        for coords in self.opening_moves(self.configured_size).to_vec() {
+            self.play(coords);
+        }
+    }
 
-        ctx.set_visuals(Visuals::dark());
+    /// Starts practicing the opening `name` on an empty board of the given size.
+    fn start_drill(&mut self, size: u8, name: String) {
+        let moves = match self.openings.get(&size).and_then(|openings| {
+            openings
+                .iter()
+                .find(|(opening, _)| *opening == name)
+                .map(|(_, moves)| moves.clone())
+        }) {
+            Some(moves) => moves,
+            None => return,
+        };
+        self.new_game(size);
+        self.drill = Some(Drill::new(size, name, moves));
     }
 
-    /// Called by the frame work to save state before shutdown.
-    /// Note that you must enable the `persistence` feature for this to work.
-    #[cfg(feature = "persistence")]
-    fn save(&mut self, storage: &mut dyn epi::Storage) {
-        epi::set_value(storage, epi::APP_KEY, self);
+    /// Progress and feedback of the running drill, see [`HexGameUi::drill`].
+    fn draw_drill(&self, ui: &mut Ui) {
+        let drill = match &self.drill {
+            Some(drill) => drill,
+            None => return,
+        };
+        let played = self.history.all_moves().count();
+        ui.label(format!(
+            "Practicing \"{}\": move {} of {}",
+            drill.name,
+            (played + 1).min(drill.move_count()),
+            drill.move_count()
+        ));
+        if drill.is_complete(played) {
+            ui.colored_label(
+                Color32::GREEN,
+                format!(
+                    "Opening complete with {} mistake{}.",
+                    drill.mistakes,
+                    if drill.mistakes == 1 { "" } else { "s" }
+                ),
+            );
+        } else {
+            match drill.feedback {
+                Some(Feedback::Correct(coords)) => ui.colored_label(
+                    Color32::GREEN,
+                    format!(
+                        "{} is right.",
+                        notation::format_coord(coords, self.coordinate_system)
+                    ),
+                ),
+                Some(Feedback::Wrong(coords)) => ui.colored_label(
+                    Color32::RED,
+                    format!(
+                        "{} is not the move of this opening, try again.",
+                        notation::format_coord(coords, self.coordinate_system)
+                    ),
+                ),
+                None => ui.label("Play the opening's first move."),
+            };
+        }
     }
 
-    /// Called each time the UI needs repainting, which may be many times per second.
-    /// Put your widgets into a `SidePanel`, `TopPanel`, `CentralPanel`, `Window` or `Area`.
-    fn update(&mut self, ctx: &egui::CtxRef, _: &epi::Frame) {
-        egui::SidePanel::left("side_panel").show(ctx, |ui| {
-            ui.heading("Options");
+    /// Replaces the current game with `moves` played on an empty board of the given size. The last
+    /// move is marked as the move of whoever plays its color now.
+    ///
+    /// A finished game is not logged, recorded or used to adjust the difficulty once more.
+    fn load_game(&mut self, size: u8, moves: &[GameMove]) -> Result<(), String> {
+        let game = board::replay(size, moves)?;
+        self.new_game(size);
+        let mut color = self.game.game.current_player;
+        let mut last = None;
+        for &game_move in moves {
+            let coords = match (game_move, last) {
+                (GameMove::Stone(coords), _) => {
+                    self.history.record(coords, color);
+                    coords
+                }
+                (GameMove::Swap, Some(first)) => {
+                    let (x, y) = board::to_xy(first);
+                    self.history.record_swap(Coords::new(y, x), color);
+                    Coords::new(y, x)
+                }
+                // `replay` only accepts a swap of the first stone.
+                (GameMove::Swap, None) => continue,
+            };
+            last = Some(coords);
+            color = board::opponent(color);
+        }
+        self.game = game;
+        self.configured_size = size;
+        if matches!(self.game.game.status, Status::Finished(_)) {
+            // The game was over before it was loaded, so it does not count as played again.
+            self.difficulty_adjusted = true;
+            self.recent_game_recorded = true;
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                self.game_logged = true;
+            }
+        }
+        if let Some(last) = last {
+            // `color` is the side to move now, the last move was its opponent's.
+            match self.controller(board::opponent(color)) {
+                Controller::Human => self.last_human_move = Some(last),
+                Controller::Ai(_) => self.last_ai_move = Some(last),
+            }
+        }
+        Ok(())
+    }
 
-            ui.horizontal(|ui| {
-                ui.label("Field Size: ");
-                ui.add(Slider::new(&mut self.configured_size, 0..=17));
-            });
+    /// The whole game in the notation of [`notation::format_game`], for sharing it.
+    fn game_text(&self) -> String {
+        notation::format_game(
+            self.game.game.board.size(),
+            &self.game_moves(),
+            CoordinateSystem::Letters,
+        )
+    }
 
-            if ui.button("Reset game...").clicked() {
-                self.game = MctsHexGame::new(self.configured_size, 0, 1);
+    /// Whether [`Self::game_text`] reproduces the current position. Edits of the position, e.g.
+    /// stones placed out of turn in analysis mode or a changed side to move, are not moves of the
+    /// game.
+    fn game_text_is_exact(&self) -> bool {
+        let board = &self.game.game.board;
+        board::replay(board.size(), &self.game_moves()).map_or(false, |replayed| {
+            board::position_hash(&replayed.game.board) == board::position_hash(board)
+                && replayed.game.current_player == self.game.game.current_player
+        })
+    }
+
+    /// Every move of the game for [`notation::format_game`].
+    fn game_moves(&self) -> Vec<GameMove> {
+        self.history.all_moves().map(|m| m.game_move()).collect()
+    }
+
+    fn load_game_text(&mut self, text: &str) -> Result<(), String> {
+        let (size, moves) = notation::parse_game(text)?;
+        self.load_game(size, &moves)
+    }
+
+    /// The name shown for the player of `color`. Falls back to the color if the name is blank.
+    fn player_name(&self, color: Color) -> &str {
+        let name = match color {
+            Color::Black => &self.red_name,
+            Color::White => &self.blue_name,
+        };
+        if name.trim().is_empty() {
+            board::color_name(color)
+        } else {
+            name
+        }
+    }
+
+    /// Whose turn it is or who won, with the color of that player.
+    fn status_line(&self) -> (Color, String) {
+        match self.game.game.status {
+            Status::Ongoing => {
+                let to_move = self.game.game.current_player;
+                (to_move, format!("{} to move", self.player_name(to_move)))
+            }
+            Status::Finished(winner) => {
+                let text = match self.resigned {
+                    Some(loser) => format!(
+                        "{} won, {} resigned",
+                        self.player_name(winner),
+                        self.player_name(loser)
+                    ),
+                    None => format!("{} won", self.player_name(winner)),
+                };
+                (winner, text)
             }
+        }
+    }
 
-            ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
-                ui.horizontal(|ui| {
-                    ui.spacing_mut().item_spacing.x = 0.0;
-                    ui.label("powered by ");
-                    ui.hyperlink_to("egui", "https://github.com/emilk/egui");
-                    ui.label(" and ");
-                    ui.hyperlink_to("eframe", "https://github.com/emilk/egui/tree/master/eframe");
-                });
-            });
+    /// The state of the game at the top of the side panel.
+    fn draw_game_status(&self, ui: &mut Ui) {
+        let (color, text) = self.status_line();
+        ui.horizontal(|ui| {
+            ui.colored_label(self.board_theme().player(color), text);
+            let moves = self.history.all_moves().count();
+            ui.label(format!(
+                "after {} move{}",
+                moves,
+                if moves == 1 { "" } else { "s" }
+            ));
         });
+    }
 
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.add(HexWidget {
-                game: &mut self.game,
-            })
+    /// Shown above the final board once the game is over.
+    fn draw_victory_banner(&mut self, ui: &mut Ui, winner: Color) {
+        ui.horizontal(|ui| {
+            let (swatch, _) = ui.allocate_exact_size(Vec2::splat(20.0), Sense::hover());
+            ui.painter()
+                .rect_filled(swatch, 4.0, self.board_theme().player(winner));
+            let text = format!("{} wins!", self.player_name(winner));
+            match self.resigned {
+                Some(loser) => {
+                    ui.heading(format!("{} ({} resigned)", text, self.player_name(loser)))
+                }
+                None => ui.heading(text),
+            };
+        });
+        ui.label(format!(
+            "The game took {} moves.",
+            self.history.all_moves().count()
+        ));
+        if let Some(id) = self.game_id() {
+            ui.horizontal(|ui| {
+                ui.label(format!("Game ID: {}", id));
+                if ui
+                    .small_button("Copy")
+                    .on_hover_text("Quote the ID when reporting this game.")
+                    .clicked()
+                {
+                    ui.output().copied_text = id;
+                }
+            });
+        }
+        ui.horizontal(|ui| {
+            if ui.button("Play again").clicked() {
+                self.reset_game();
+            }
+            if ui.button("Change settings").clicked() {
+                self.show_side_panel = true;
+            }
         });
+        ui.separator();
     }
-}
 
-fn get_hex_shape(pos: Pos2) -> Vec<Pos2> {
-    let factor = 1.1;
-    hex_coords()
-        .into_iter()
-        .map(|offset| pos + offset * (HEX_SIZE * 0.5 * factor))
-        .collect()
-}
+    /// Identifies the current game in deterministic mode, see [`ai::game_id`].
+    fn game_id(&self) -> Option<String> {
+        if !self.deterministic {
+            return None;
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        let ponder = self.ponder;
+        #[cfg(target_arch = "wasm32")]
+        let ponder = false;
+        let settings = GameSettings {
+            size: self.game.game.board.size(),
+            controllers: self.controllers,
+            opponent: self.opponent,
+            resign_threshold: self.ai_resign_threshold,
+            swap_rule: self.swap_rule,
+            ponder,
+        };
+        let moves: Vec<_> = self.history.all_moves().map(|m| m.game_move()).collect();
+        Some(ai::game_id(&settings, self.seed, &moves))
+    }
 
-struct HexWidget<'a> {
-    game: &'a mut MctsHexGame,
-}
+    /// The chance that `color` wins according to the AI's latest rating of a move, see
+    /// [`crate::history::Move::eval`].
+    ///
+    /// Ratings are from the mover's point of view, so they are flipped for moves of the opponent.
+    fn win_probability(&self, color: Color) -> Option<f32> {
+        let (mover, eval) = self
+            .history
+            .all_moves()
+            .filter_map(|m| Some((m.color, m.eval?)))
+            .last()?;
+        Some(if mover == color { eval } else { 1.0 - eval })
+    }
 
-fn player_to_color(player: Color) -> Color32 {
-    match player {
-        Color::Black => Color32::RED,
-        Color::White => Color32::BLUE,
+    /// The side whose chances the evaluation bar states: the human's if only one side is human,
+    /// otherwise the first player's.
+    fn evaluation_perspective(&self) -> Color {
+        match self
+            .controllers
+            .map(|controller| controller == Controller::Human)
+        {
+            [false, true] => Color::White,
+            _ => Color::Black,
+        }
     }
-}
 
-impl<'a> Widget for HexWidget<'a> {
-    fn ui(mut self, ui: &mut Ui) -> Response {
-        match self.game().status {
-            Status::Ongoing => self.draw_game(ui),
-            Status::Finished(player) => self.draw_victory_screen(ui, player),
+    /// A bar split between the players' colors in proportion to their chances of winning, after the
+    /// AI's latest search.
+    fn draw_evaluation_bar(&self, ui: &mut Ui) {
+        let perspective = self.evaluation_perspective();
+        let probability = match self.win_probability(perspective) {
+            Some(probability) => probability,
+            None => {
+                ui.label("The AI rates the position after its first move.");
+                return;
+            }
+        };
+        let theme = self.board_theme();
+        let (rect, _) =
+            ui.allocate_exact_size(Vec2::new(ui.available_width(), 18.0), Sense::hover());
+        let split = rect.left() + rect.width() * probability;
+        let (own, other) = (
+            egui::Rect::from_min_max(rect.min, egui::pos2(split, rect.max.y)),
+            egui::Rect::from_min_max(egui::pos2(split, rect.min.y), rect.max),
+        );
+        let painter = ui.painter();
+        painter.rect_filled(own, 0.0, theme.player(perspective));
+        painter.rect_filled(other, 0.0, theme.player(board::opponent(perspective)));
+        painter.text(
+            rect.center(),
+            Align2::CENTER_CENTER,
+            format!(
+                "{}: {:.0}% to win",
+                self.player_name(perspective),
+                probability * 100.0
+            ),
+            TextStyle::Body,
+            Color32::WHITE,
+        );
+    }
+
+    /// Shows how close each player is to connecting their edges, see [`board::remaining_distance`].
+    fn draw_connection_progress(&self, ui: &mut Ui) {
+        let board = &self.game.game.board;
+        let size = board.size();
+        for color in [Color::Black, Color::White] {
+            let (progress, text) = match board::remaining_distance(board, color) {
+                Some(0) => (1.0, "connected".to_owned()),
+                Some(remaining) => (
+                    1.0 - remaining as f32 / size as f32,
+                    format!(
+                        "{} more stone{} needed",
+                        remaining,
+                        if remaining == 1 { "" } else { "s" }
+                    ),
+                ),
+                None => (0.0, "cut off".to_owned()),
+            };
+            ui.horizontal(|ui| {
+                ui.colored_label(self.board_theme().player(color), self.player_name(color));
+                ui.add(egui::ProgressBar::new(progress).text(text));
+            });
         }
     }
-}
 
-impl<'a> HexWidget<'a> {
-    fn game(&mut self) -> &mut Game {
-        &mut self.game.game
+    /// Explains the first player's advantage on the current board size, shown before the first move.
+    fn draw_first_move_advantage(&mut self, ui: &mut Ui) {
+        let size = self.game.game.board.size();
+        let estimating = matches!(&self.advantage_job, Some((job_size, _)) if *job_size == size);
+        match self.first_move_advantage.get(&size) {
+            Some(rate) => {
+                ui.label(format!(
+                    "{} moves first and won {:.0}% of {} AI self-play games on this board size.",
+                    board::color_name(Color::Black),
+                    rate * 100.0,
+                    SELF_PLAY_GAMES
+                ));
+            }
+            None if estimating => {
+                ui.label("Estimating the first move advantage...");
+            }
+            None => {
+                if ui
+                    .add_enabled(self.advantage_job.is_none(), egui::Button::new("Estimate first move advantage"))
+                    .on_hover_text("Lets a quick AI play against itself. This can take a moment on large boards.")
+                    .clicked()
+                {
+                    // A separate generator keeps the estimate from changing the AI's moves in the game.
+                    let mut rng = SmallRng::seed_from_u64(size as u64);
+                    let receiver = spawn_search(move || {
+                        headless::first_player_win_rate(
+                            size,
+                            &self_play_config(),
+                            SELF_PLAY_GAMES,
+                            &mut rng,
+                        )
+                    });
+                    self.advantage_job = Some((size, receiver));
+                }
+            }
+        }
+        ui.label(
+            "With perfect play, the first player always wins. This is why the swap rule exists.",
+        );
     }
 
-    fn draw_victory_screen(self, ui: &mut Ui, player: Color) -> Response {
-        let text = match player {
-            Color::Black => "Player Red wins!",
-            Color::White => "Player Blue wins!",
+    /// Runs one line typed into the engine console and appends its output.
+    fn run_console_command(&mut self, line: &str) {
+        self.console_output.push(format!("> {}", line));
+        let output = match console::parse_command(line) {
+            Ok(Command::Go(_) | Command::Eval) if self.search_job.is_some() => {
+                "busy, wait for the running search".to_owned()
+            }
+            Ok(Command::Go(iterations)) => {
+                let config = match iterations {
+                    Some(iterations) => AiConfig {
+                        iterations,
+                        budget: SearchBudget::Iterations,
+                        ..self.ai_config()
+                    },
+                    None => self.ai_config(),
+                };
+                self.start_search(SearchPurpose::ConsoleGo, config);
+                return;
+            }
+            Ok(Command::Eval) => {
+                self.start_search(SearchPurpose::ConsoleEval, self.ai_config());
+                return;
+            }
+            Ok(Command::SetPosition(text)) => match self.load_game_text(&text) {
+                Ok(()) => "ok".to_owned(),
+                Err(error) => error,
+            },
+            Ok(Command::GetPosition) => notation::format_game(
+                self.game.game.board.size(),
+                &self.game_moves(),
+                self.coordinate_system,
+            ),
+            Ok(Command::Help) => console::HELP.to_owned(),
+            Err(error) => error,
         };
-        ui.heading(text)
+        self.console_output.push(output);
     }
 
-    fn draw_game(mut self, ui: &mut Ui) -> Response {
-        let board = &self.game().board;
-        let size = board.size();
+    /// The current game for [`summary::summary_to_json`].
+    fn game_summary(&self) -> GameSummary {
+        GameSummary {
+            size: self.game.game.board.size(),
+            players: [Color::Black, Color::White].map(|color| self.player_name(color).to_owned()),
+            moves: self
+                .history
+                .all_moves()
+                .map(|m| MoveSummary {
+                    coords: m.coords,
+                    color: m.color,
+                    eval: m.eval,
+                })
+                .collect(),
+            result: match self.game.game.status {
+                Status::Finished(winner) => Some(GameResult {
+                    winner,
+                    resigned: self.resigned.is_some(),
+                }),
+                Status::Ongoing => None,
+            },
+            started: self.game_started,
+            finished: self.game_finished,
+        }
+    }
 
-        let base_size = size as f32 * HEX_SIZE;
-        let response = ui.allocate_response(
-            Vec2::new(base_size * 1.5, base_size),
-            Sense::click_and_drag(),
-        );
-        let rect = response.rect;
-        let painter = ui.painter_at(rect);
-        let mut base_offset = rect.left_top();
-        base_offset.x += HEX_SIZE;
-        base_offset.y += HEX_SIZE;
+    /// Adds the game to the recent games once it is over.
+    fn record_recent_game(&mut self) {
+        let winner = match self.game.game.status {
+            Status::Finished(winner) => winner,
+            Status::Ongoing => return,
+        };
+        if self.recent_game_recorded {
+            return;
+        }
+        self.recent_game_recorded = true;
+        self.game_finished = unix_time();
 
-        let mut closest_coord = None;
-        let mut closest_distance = f32::MAX;
+        let size = self.game.game.board.size();
+        let moves = self.game_moves();
+        self.recent_games.push_front(RecentGame {
+            game: notation::format_game(size, &moves, CoordinateSystem::Letters),
+            summary: format!(
+                "{} beat {} in {} moves on {}x{}",
+                self.player_name(winner),
+                self.player_name(board::opponent(winner)),
+                moves.len(),
+                size,
+                size
+            ),
+        });
+        self.recent_games.truncate(MAX_RECENT_GAMES);
+        self.recent_thumbnails.clear();
+    }
 
-        let pointer = &ui.input().pointer;
+    /// Lists the recent games with a thumbnail of their final positions. Clicking one loads it.
+    fn draw_recent_games(&mut self, ui: &mut Ui) {
+        if self.recent_games.is_empty() {
+            ui.label("Finished games show up here.");
+            return;
+        }
+        if self.recent_thumbnails.len() != self.recent_games.len() {
+            self.recent_thumbnails = self
+                .recent_games
+                .iter()
+                .map(|recent| {
+                    let (size, moves) = notation::parse_game(&recent.game).ok()?;
+                    Some(board::replay(size, &moves).ok()?.game.board)
+                })
+                .collect();
+        }
+
+        let theme = self.board_theme();
+        let mut load = None;
+        for (recent, thumbnail) in self.recent_games.iter().zip(&self.recent_thumbnails) {
+            ui.horizontal(|ui| match thumbnail {
+                Some(board) => {
+                    ui.add(Thumbnail { board, theme });
+                    ui.vertical(|ui| {
+                        ui.label(&recent.summary);
+                        if ui.small_button("Load").clicked() {
+                            load = Some(recent.game.clone());
+                        }
+                    });
+                }
+                // E.g. state restored from a version with a different notation.
+                None => {
+                    ui.label(format!("{} (cannot be replayed)", recent.summary));
+                }
+            });
+        }
+        if let Some(game) = load {
+            // Only games that could be replayed for their thumbnail can be loaded.
+            self.load_game_text(&game).ok();
+        }
+    }
 
-        let pos = |x, y| {
-            let x = HEX_SIZE * x as f32 + y as f32 * HEX_SIZE * 0.5;
-            let y = HEX_SIZE * y as f32 * 0.87;
-            base_offset + Vec2::new(x, y)
+    /// Adapts the AI's strength to the result once the game is over, if adaptive difficulty is on.
+    fn adjust_difficulty(&mut self) {
+        let winner = match self.game.game.status {
+            Status::Finished(winner) => winner,
+            Status::Ongoing => return,
         };
+        // Games from analysis mode were not played against the AI.
+        if !self.adaptive_difficulty || self.difficulty_adjusted || self.analysis_mode {
+            return;
+        }
+        self.difficulty_adjusted = true;
 
-        for x in 0..size {
-            for y in 0..size {
-                if let Some(cursor_pos) = pointer.hover_pos() {
-                    let selection_range_sq = HEX_SIZE * HEX_SIZE;
-                    let distance_sq = cursor_pos.distance_sq(pos(x, y));
-                    let is_within_selection_range =
-                        cursor_pos.distance_sq(pos(x, y)) < selection_range_sq;
-                    let is_closest = distance_sq < closest_distance;
-                    if is_within_selection_range && is_closest {
-                        closest_distance = distance_sq;
-                        closest_coord = Some((x, y))
+        // Only games between a human and the AI say something about the AI's strength.
+        let human = match self.controllers {
+            [Controller::Human, Controller::Ai(_)] => Color::Black,
+            [Controller::Ai(_), Controller::Human] => Color::White,
+            _ => return,
+        };
+        let step = self.difficulty_step;
+        if let Controller::Ai(config) =
+            &mut self.controllers[board::color_index(board::opponent(human))]
+        {
+            match &mut config.budget {
+                SearchBudget::Iterations => {
+                    config.iterations = if winner == human {
+                        config.iterations.saturating_add(step)
+                    } else {
+                        config.iterations.saturating_sub(step)
                     }
+                    .clamp(MIN_AI_ITERATIONS, MAX_AI_ITERATIONS);
+                }
+                SearchBudget::Time(seconds) => {
+                    *seconds = if winner == human {
+                        *seconds + DIFFICULTY_TIME_STEP
+                    } else {
+                        *seconds - DIFFICULTY_TIME_STEP
+                    }
+                    .clamp(MIN_AI_TIME_BUDGET, MAX_AI_TIME_BUDGET);
                 }
             }
         }
+    }
 
-        for x in 0..size {
-            for y in 0..size {
-                let color = match board.get_color(Coords::new(x, y)) {
-                    Some(Color::Black) => Color32::RED,
-                    Some(Color::White) => Color32::BLUE,
-                    None => Color32::LIGHT_GRAY,
-                };
+    fn controller(&self, color: Color) -> Controller {
+        self.controllers[board::color_index(color)]
+    }
 
-                let hex_shape = get_hex_shape(pos(x, y));
-                let default_stroke = Stroke::new(1.0, Color32::DARK_GRAY);
-                let line = Shape::convex_polygon(hex_shape, color, default_stroke);
-                painter.add(line);
-            }
-        }
-
-        if let Some((x, y)) = closest_coord {
-            if board.get_color(Coords::new(x, y)).is_none() {
-                let player_color = player_to_color(self.game().current_player);
-                let hex_shape = get_hex_shape(pos(x, y));
-                let line = Shape::closed_line(hex_shape, Stroke::new(4.0, player_color));
-                painter.add(line);
-            }
-
-            if response.clicked() {
-                self.game.play(Coords::new(x, y)).ok();
-
-                if self.game.get_winner().is_none() {
-                    let mcts: Mcts<
-                        MctsHexGame,
-                        HexNodeContent,
-                        SmallRng,
-                        UctSelection,
-                        FullExpansion,
-                        ShuffledPlayout,
-                        UctUpdate,
-                        SelectRobustChild,
-                        ConstIterationCount,
-                    > = Mcts::new(
-                        UctSelection {
-                            exploration_parameter: 0.5,
-                        },
-                        FullExpansion,
-                        ShuffledPlayout,
-                        UctUpdate,
-                        SelectRobustChild,
-                        ConstIterationCount::new(10_000),
-                    );
+    /// Lets the AI play both sides, or hands the human's sides back to them. A human side is played
+    /// with the other side's AI settings, so both play equally strong unless changed.
+    ///
+    /// Stopping drops the AI's pending search or move, so the human continues from the position on
+    /// the board. Changes to the AI settings while watching are kept.
+    fn set_watching(&mut self, watch: bool) {
+        if watch == self.watching.is_some() {
+            return;
+        }
+        if watch {
+            self.watching = Some(
+                self.controllers
+                    .map(|controller| controller == Controller::Human),
+            );
+            let config = self
+                .controllers
+                .iter()
+                .find_map(|controller| match controller {
+                    Controller::Ai(config) => Some(*config),
+                    Controller::Human => None,
+                })
+                .unwrap_or_default();
+            for controller in &mut self.controllers {
+                if *controller == Controller::Human {
+                    *controller = Controller::Ai(config);
+                }
+            }
+        } else if let Some(humans) = self.watching.take() {
+            for (controller, human) in self.controllers.iter_mut().zip(humans) {
+                if human {
+                    *controller = Controller::Human;
+                }
+            }
+            self.cancel_ai_move();
+        }
+    }
+
+    /// Settings for searches that are not made for a player, e.g. from the engine console.
+    ///
+    /// Those of the side to move if the AI plays it, otherwise those of the other side.
+    fn ai_config(&self) -> AiConfig {
+        let to_move = self.game.game.current_player;
+        [to_move, board::opponent(to_move)]
+            .iter()
+            .find_map(|&color| match self.controller(color) {
+                Controller::Ai(config) => Some(config),
+                Controller::Human => None,
+            })
+            .unwrap_or_default()
+    }
 
-                    let mut rng = SmallRng::from_entropy();
-                    let result = mcts.suggest_action(self.game, &mut rng);
-                    let action = result
-                        .tree
-                        .get_content(result.node_id.expect("AI did not set node ID"))
-                        .get_action()
-                        .expect("Failed to retrieve a valid action");
+    /// How `color` is played, for the game log.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn describe_controller(&self, color: Color) -> String {
+        match self.controller(color) {
+            Controller::Human => "human".to_owned(),
+            Controller::Ai(config) => format!(
+                "{} AI with {} iterations, exploration {}{}",
+                self.opponent.name(),
+                config.iterations,
+                config.exploration_parameter,
+                config
+                    .late_exploration_parameter
+                    .map_or_else(String::new, |late| format!(" to {}", late))
+            ),
+        }
+    }
 
-                    self.game.play(action).expect("Failed to play AI move");
+    /// Starts solving the current position on a background thread if the oracle is on and has no
+    /// verdict for it yet, and collects the verdict once it arrives.
+    ///
+    /// A job for another position than the current one is dropped, as is any job while the oracle
+    /// is off. Its thread still runs to its end, but its verdict is discarded.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn update_oracle(&mut self, frame: &epi::Frame) {
+        if !self.show_oracle {
+            self.oracle_job = None;
+            return;
+        }
+        let key = board::position_key(&self.game);
+        if let Some((job_key, receiver)) = &self.oracle_job {
+            let job_key = *job_key;
+            match receiver.try_recv() {
+                Ok(verdict) if job_key == key => {
+                    self.oracle = Some((job_key, verdict));
+                    self.oracle_job = None;
                 }
+                Err(TryRecvError::Empty) if job_key == key => return,
+                // The position has changed since the job started.
+                _ => self.oracle_job = None,
             }
         }
 
-        response
+        let solved = matches!(&self.oracle, Some((solved_key, _)) if *solved_key == key);
+        let ongoing = matches!(self.game.game.status, Status::Ongoing);
+        if solved || !ongoing || self.game.game.board.size() > MAX_ORACLE_SIZE {
+            return;
+        }
+        let game = self.game.clone();
+        let frame = frame.clone();
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            if let Some(verdict) = oracle::solve(&game) {
+                if sender.send(verdict).is_ok() {
+                    frame.request_repaint();
+                }
+            }
+        });
+        self.oracle_job = Some((key, receiver));
+    }
+
+    /// Starts pondering when the human is to move against the AI, and drops the pondering job once
+    /// the human leaves its position other than by moving, e.g. by undoing.
+    ///
+    /// A search cannot be interrupted, so a dropped job runs to its end and its result is discarded.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn update_ponder(&mut self, frame: &epi::Frame) {
+        let key = board::position_key(&self.game);
+        if let Some((job_key, _)) = &self.ponder_job {
+            // After the human's move, the job is kept until the AI replies.
+            if *job_key == key || !self.is_human_turn() {
+                return;
+            }
+            self.ponder_job = None;
+        }
+
+        let human = self.game.game.current_player;
+        let config = match self.controller(board::opponent(human)) {
+            Controller::Ai(config) => config,
+            Controller::Human => return,
+        };
+        let ongoing = matches!(self.game.game.status, Status::Ongoing);
+        let playing =
+            !self.analysis_mode && self.drill.is_none() && self.opponent == Opponent::Mcts;
+        if !self.ponder || !ongoing || !playing || self.controller(human) != Controller::Human {
+            return;
+        }
+        let game = self.game.clone();
+        let frame = frame.clone();
+        let mut rng = self.ai_search_rng();
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            if let Some((predicted, reply)) = ai::ponder(&game, &config, &mut rng) {
+                let mut after = game;
+                if after.play(predicted).is_ok()
+                    && sender.send((board::position_key(&after), reply)).is_ok()
+                {
+                    frame.request_repaint();
+                }
+            }
+        });
+        self.ponder_job = Some((key, receiver));
+    }
+
+    /// The pondered reply, if the human played the predicted move or reached the predicted
+    /// position otherwise.
+    ///
+    /// In deterministic mode, the AI waits for the pondering to finish so the game does not depend
+    /// on how fast the human moved. The job is kept then, and [`Pondered::Pending`] returned.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn take_pondered_reply(&mut self) -> Pondered {
+        let (key, reply) = match &self.ponder_job {
+            Some((_, receiver)) => match receiver.try_recv() {
+                Ok(pondered) => pondered,
+                Err(TryRecvError::Empty) if self.deterministic => return Pondered::Pending,
+                Err(_) => {
+                    self.ponder_job = None;
+                    return Pondered::Missed;
+                }
+            },
+            None => return Pondered::Missed,
+        };
+        self.ponder_job = None;
+        if key == board::position_key(&self.game) {
+            Pondered::Reply(reply)
+        } else {
+            Pondered::Missed
+        }
+    }
+
+    /// Writes the game to the log once it is over, if logging is enabled.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn log_finished_game(&mut self) {
+        let winner = match self.game.game.status {
+            Status::Finished(winner) => winner,
+            Status::Ongoing => return,
+        };
+        if !self.log_games || self.game_logged {
+            return;
+        }
+        self.game_logged = true;
+
+        let moves = self.game_moves();
+        let game_id = self
+            .game_id()
+            .map_or_else(String::new, |id| format!(", game ID {}", id));
+        let entry = format!(
+            "{} wins{} ({}: {}, {}: {}{})\n{}",
+            self.player_name(winner),
+            if self.resigned.is_some() {
+                " by resignation"
+            } else {
+                ""
+            },
+            self.player_name(Color::Black),
+            self.describe_controller(Color::Black),
+            self.player_name(Color::White),
+            self.describe_controller(Color::White),
+            game_id,
+            notation::format_game(
+                self.game.game.board.size(),
+                &moves,
+                CoordinateSystem::Letters
+            )
+        );
+        self.game_log_error = crate::game_log::append_entry(Path::new(&self.game_log_path), &entry)
+            .err()
+            .map(|error| format!("Could not write the game log: {}", error));
+    }
+
+    /// Keeps the page URL pointing at the current game, so it can be shared as a link. After an edit
+    /// of the position, the URL keeps the game before the edit.
+    #[cfg(target_arch = "wasm32")]
+    fn update_shared_url(&mut self) {
+        if !self.game_text_is_exact() {
+            return;
+        }
+        let shared = self.game_text();
+        if shared != self.shared_game {
+            crate::web::write_shared_game(&shared);
+            self.shared_game = shared;
+        }
+    }
+
+    /// Whether a human makes the next move. In analysis mode, the human plays both sides.
+    fn is_human_turn(&self) -> bool {
+        self.analysis_mode
+            || self.drill.is_some()
+            || self.controller(self.game.game.current_player) == Controller::Human
+    }
+
+    /// Schedules the AI's move at input time `now` plus the AI delay, if the AI is to move and its
+    /// move is not scheduled yet.
+    fn schedule_ai_move(&mut self, now: f64) {
+        let idle = self.ai_move_due.is_none()
+            && !self.ai_thinking()
+            && self.held_ai_move.is_none()
+            && self.ai_preview.is_none();
+        let ongoing = matches!(self.game.game.status, Status::Ongoing);
+        // Without `auto_start_ai`, an AI that moves first waits for "AI move".
+        let started = self.auto_start_ai || !self.is_untouched();
+        if idle && ongoing && started && !self.is_human_turn() && !self.ai_gave_up() {
+            // With `instant_forced_moves`, the delay is applied after the search instead.
+            let delay = if self.instant_forced_moves {
+                0.0
+            } else {
+                self.effective_ai_delay()
+            };
+            self.ai_move_due = Some(now + delay as f64);
+        }
+    }
+
+    /// Drives the AI at input time `now`, whether or not the human has done anything: schedules its
+    /// move when it is to move and queues the events of its pending move that are due.
+    ///
+    /// Returns the seconds until the next pending step is due, if one is waiting. A previewed move
+    /// is not included, it needs a repaint every frame anyway.
+    fn advance_ai(&mut self, now: f64) -> Option<f64> {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.poll_ai_search(now);
+        self.schedule_ai_move(now);
+        let mut next = None;
+        if let Some(due) = self.ai_move_due {
+            if self.analysis_mode {
+                self.ai_move_due = None;
+            } else if now >= due {
+                self.ai_move_due = None;
+                self.move_events.push(MoveEvent::AiReply {
+                    time: now,
+                    generation: self.ai_generation,
+                });
+            } else {
+                next = Some(due - now);
+            }
+        }
+        if let Some((coords, due)) = self.held_ai_move {
+            if self.analysis_mode {
+                self.held_ai_move = None;
+            } else if now >= due {
+                self.held_ai_move = None;
+                self.move_events.push(MoveEvent::AiChosen {
+                    coords,
+                    time: now,
+                    generation: self.ai_generation,
+                });
+            } else {
+                next = Some(due - now);
+            }
+        }
+        if let Some((coords, due)) = self.ai_preview {
+            if self.analysis_mode {
+                self.ai_preview = None;
+            } else if now >= due {
+                self.ai_preview = None;
+                self.move_events.push(MoveEvent::AiMove {
+                    coords,
+                    generation: self.ai_generation,
+                });
+            }
+        }
+        next
+    }
+
+    /// Colors and line widths of the board.
+    fn board_theme(&self) -> Theme {
+        if self.high_contrast {
+            Theme::high_contrast()
+        } else {
+            Theme::standard()
+        }
+    }
+
+    /// The interactive board for the current game.
+    ///
+    /// `viewport` is the space the board should fit into.
+    fn board_widget(&mut self, size_overlay: Option<u8>, viewport: Vec2) -> HexWidget<'_> {
+        let position = board::position_hash(&self.game.game.board);
+        #[cfg(not(target_arch = "wasm32"))]
+        let oracle: &[(Coords, bool)] = match &self.oracle {
+            Some((key, verdict)) if self.show_oracle && *key == board::position_key(&self.game) => {
+                verdict.as_slice()
+            }
+            _ => &[],
+        };
+        #[cfg(target_arch = "wasm32")]
+        let oracle: &[(Coords, bool)] = &[];
+        HexWidget {
+            game: &self.game,
+            sizing: self.board_sizing(),
+            theme: self.board_theme(),
+            viewport,
+            last_search: &self.last_search,
+            show_candidates: self.show_candidates,
+            show_bridges: self.show_bridges,
+            show_coordinates: self.show_coordinates,
+            coordinate_system: self.coordinate_system,
+            size_overlay,
+            animations: self.animations,
+            victory_flow_speed: self.victory_flow_speed,
+            hover_highlight: &mut self.hover_highlight,
+            ai_preview: self.ai_preview.map(|(coords, _)| coords),
+            rejected_click: self.rejected_click,
+            debug_overlay: self.show_debug_overlay,
+            oracle,
+            last_human_move: self.last_human_move,
+            last_ai_move: self.last_ai_move,
+            drill_correct: match self.drill.as_ref().and_then(|drill| drill.feedback) {
+                Some(Feedback::Correct(coords)) => Some(coords),
+                _ => None,
+            },
+            hint: self
+                .hint
+                .filter(|&(_, hash)| hash == position)
+                .map(|(coords, _)| coords),
+            drag_to_place: self.drag_to_place,
+            move_events: &mut self.move_events,
+        }
+    }
+
+    fn board_sizing(&self) -> BoardSizing {
+        BoardSizing {
+            // Keeps the coordinate labels and the edge labels pushed out by them on screen.
+            margin: self.board_margin
+                + if self.show_coordinates {
+                    COORDINATE_LABEL_SPACE
+                } else {
+                    0.0
+                },
+            cell_gap: self.cell_gap,
+            min_cell_size: self.min_cell_size,
+            max_cell_size: self.max_cell_size,
+        }
+    }
+
+    /// Leaves analysis mode and continues the edited position with the current side to move. The AI
+    /// moves as soon as it is its turn.
+    ///
+    /// Fails if the position is already won.
+    fn play_from_here(&mut self) -> Result<(), String> {
+        if let Status::Finished(winner) = self.game.game.status {
+            return Err(format!(
+                "{} has already won this position",
+                board::color_name(winner)
+            ));
+        }
+        self.analysis_mode = false;
+        self.resigned = None;
+        self.last_search = None;
+        Ok(())
+    }
+
+    /// Forgets the AI's pending move, including one already queued as a [`MoveEvent`].
+    fn cancel_ai_move(&mut self) {
+        self.ai_move_due = None;
+        self.held_ai_move = None;
+        self.ai_preview = None;
+        // The search cannot be interrupted. It runs to its end and its result is dropped.
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.ai_search = None;
+        }
+        self.ai_generation += 1;
+    }
+
+    /// Whether the AI is searching on a background thread.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn ai_thinking(&self) -> bool {
+        self.ai_search.is_some()
+    }
+
+    /// Web builds have no threads, there the AI searches while the frame is drawn.
+    #[cfg(target_arch = "wasm32")]
+    fn ai_thinking(&self) -> bool {
+        false
+    }
+
+    /// Whether a search other than the AI's own, see [`spawn_search`], has not finished yet.
+    fn searching_in_background(&self) -> bool {
+        !self.blunder_checks.is_empty() || self.advantage_job.is_some() || self.search_job.is_some()
+    }
+
+    /// Searches the current position in the background for `purpose`, see
+    /// [`Self::poll_search_job`].
+    fn start_search(&mut self, purpose: SearchPurpose, config: AiConfig) {
+        let game = self.game.clone();
+        // A forced move is played like the AI's own.
+        let mut rng = if purpose == SearchPurpose::ForcedMove {
+            self.ai_search_rng()
+        } else {
+            SmallRng::seed_from_u64(rand::Rng::gen(&mut self.analysis_rng))
+        };
+        let receiver = spawn_search(move || ai::suggest(&game, &config, &mut rng));
+        self.search_job = Some((purpose, board::position_key(&self.game), receiver));
+    }
+
+    /// Uses the result of the search started by [`Self::start_search`] once it has arrived. A result
+    /// for a position other than the current one is dropped.
+    fn poll_search_job(&mut self) {
+        match self.search_job {
+            Some((purpose @ (SearchPurpose::Hint | SearchPurpose::ForcedMove), ..)) => {
+                match (purpose, self.best_move()) {
+                    (SearchPurpose::Hint, Some(action)) => {
+                        let position = board::position_hash(&self.game.game.board);
+                        self.hint = Some((action, position));
+                    }
+                    (SearchPurpose::ForcedMove, Some(action)) => self.play_ai_move(action),
+                    _ => {}
+                }
+                return;
+            }
+            Some(_) => {}
+            None => return,
+        }
+
+        let (purpose, key, suggestion) = match &self.search_job {
+            Some((purpose, key, receiver)) => match receiver.try_recv() {
+                Ok(suggestion) => (*purpose, *key, suggestion),
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => (*purpose, *key, None),
+            },
+            None => return,
+        };
+        self.search_job = None;
+        if key != board::position_key(&self.game) {
+            self.console_output
+                .push("the position changed during the search".to_owned());
+            return;
+        }
+        let output = match (purpose, suggestion) {
+            (SearchPurpose::ConsoleGo, Some(suggestion)) => {
+                let output = format!(
+                    "bestmove {} ({:.0}% win)",
+                    notation::format_coord(suggestion.action, self.coordinate_system),
+                    suggestion.win_probability * 100.0
+                );
+                self.last_search = Some(suggestion);
+                output
+            }
+            (SearchPurpose::ConsoleGo, None) => "no move found".to_owned(),
+            (SearchPurpose::ConsoleEval, Some(suggestion)) => format!(
+                "{} to move: {:.0}% win",
+                board::color_name(self.game.game.current_player),
+                suggestion.win_probability * 100.0
+            ),
+            (SearchPurpose::ConsoleEval, None) => "no evaluation, the game is over".to_owned(),
+            (SearchPurpose::Hint | SearchPurpose::ForcedMove, _) => return,
+        };
+        self.console_output.push(output);
+    }
+
+    /// The move the AI would play for the side to move, once the hint or "AI move" search of
+    /// [`Self::start_search`] has finished for the current position, see [`Self::take_move`].
+    ///
+    /// Returns `None` while the search runs. A search for another position or without a move is
+    /// dropped, and returns `None` as well.
+    fn best_move(&mut self) -> Option<Coords> {
+        let (key, suggestion) = match &self.search_job {
+            Some((SearchPurpose::Hint | SearchPurpose::ForcedMove, key, receiver)) => {
+                match receiver.try_recv() {
+                    Ok(suggestion) => (*key, suggestion),
+                    Err(TryRecvError::Empty) => return None,
+                    Err(TryRecvError::Disconnected) => (*key, None),
+                }
+            }
+            _ => return None,
+        };
+        self.search_job = None;
+        let suggestion = suggestion.filter(|_| key == board::position_key(&self.game))?;
+        Some(self.take_move(suggestion))
+    }
+
+    /// Reads the move out of a finished search and keeps the search's statistics in `last_search`.
+    /// Hints, the "AI move" button and the AI's own moves all go through this.
+    fn take_move(&mut self, suggestion: Suggestion) -> Coords {
+        let action = suggestion.action;
+        self.last_search = Some(suggestion);
+        action
+    }
+
+    /// Stores the estimate of the first move advantage once it has arrived.
+    fn poll_advantage_job(&mut self) {
+        if let Some((size, receiver)) = &self.advantage_job {
+            match receiver.try_recv() {
+                Ok(rate) => {
+                    if let Some(rate) = rate {
+                        self.first_move_advantage.insert(*size, rate);
+                    }
+                    self.advantage_job = None;
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => self.advantage_job = None,
+            }
+        }
+    }
+
+    /// Finishes the AI's turn with the suggestion of its search, once it has arrived.
+    ///
+    /// A reply for a position other than the current one is dropped. If the search panicked or found
+    /// no move, the AI gives up on the position instead of searching it again on every frame.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn poll_ai_search(&mut self, now: f64) {
+        let (key, result) = match &self.ai_search {
+            Some((key, receiver)) => match receiver.try_recv() {
+                Ok(reply) => (*key, reply),
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => (*key, None),
+            },
+            None => return,
+        };
+        self.ai_search = None;
+        if key != board::position_key(&self.game) {
+            return;
+        }
+        match result {
+            Some(reply) => self.finish_ai_reply(reply, now),
+            None => self.ai_failed = Some(key),
+        }
+    }
+
+    /// Whether the AI's search failed in the current position, see [`Self::poll_ai_search`].
+    fn ai_gave_up(&self) -> bool {
+        self.ai_failed == Some(board::position_key(&self.game))
+    }
+
+    /// Applies the moves queued since the last frame, in order. Returns whether the board needs to
+    /// be redrawn.
+    fn apply_move_events(&mut self) -> bool {
+        let mut applied = false;
+        for event in std::mem::take(&mut self.move_events) {
+            applied |= match event {
+                MoveEvent::Place { coords, time } => self.place_human_move(coords, time),
+                MoveEvent::Remove(coords) => self.remove_stone(coords),
+                MoveEvent::ClickedOffBoard => match self.off_board_click {
+                    OffBoardClick::Ignore => false,
+                    OffBoardClick::ClearHint => self.hint.take().is_some(),
+                },
+                MoveEvent::AiReply { time, generation } if generation == self.ai_generation => {
+                    self.play_ai_reply(time);
+                    true
+                }
+                MoveEvent::AiChosen {
+                    coords,
+                    time,
+                    generation,
+                } if generation == self.ai_generation => {
+                    self.commit_ai_move(coords, time);
+                    true
+                }
+                MoveEvent::AiMove { coords, generation } if generation == self.ai_generation => {
+                    self.play_ai_move(coords);
+                    true
+                }
+                // Queued for a game or position that has been replaced since.
+                MoveEvent::AiReply { .. }
+                | MoveEvent::AiChosen { .. }
+                | MoveEvent::AiMove { .. } => false,
+            };
+        }
+        applied
+    }
+
+    /// Plays `coords` for the human after a click at input time `now`.
+    ///
+    /// Clicks within [`CLICK_DEBOUNCE`] of the last move are ignored. Clicks on occupied cells or
+    /// while the AI's reply is pending are rejected, which flashes the cell. Returns whether the
+    /// board needs to be redrawn.
+    fn place_human_move(&mut self, coords: Coords, now: f64) -> bool {
+        let debounced = self
+            .last_move_time
+            .map_or(false, |last| now - last < CLICK_DEBOUNCE);
+        if debounced {
+            return false;
+        }
+        let occupied = self.game.game.board.get_color(coords).is_some();
+        let ai_pending = self.ai_move_due.is_some()
+            || self.ai_thinking()
+            || self.held_ai_move.is_some()
+            || self.ai_preview.is_some();
+        if occupied || ai_pending || !self.is_human_turn() {
+            self.rejected_click = Some((coords, now));
+            return true;
+        }
+
+        if let Some(drill) = &mut self.drill {
+            if !drill.check(coords, self.history.all_moves().count()) {
+                self.rejected_click = Some((coords, now));
+                return true;
+            }
+        }
+
+        let before = self.detect_blunders.then(|| self.game.clone());
+        if !self.play(coords) {
+            self.rejected_click = Some((coords, now));
+            return true;
+        }
+        self.last_move_time = Some(now);
+        self.last_human_move = Some(coords);
+        self.hint = None;
+        if let Some(before) = before {
+            self.start_blunder_check(before, coords);
+        }
+        if let Some(drill) = &self.drill {
+            if drill.is_complete(self.history.all_moves().count()) {
+                self.completed_drills.insert(drill.key());
+            }
+        }
+        true
+    }
+
+    /// Removes the stone on `coords` in analysis mode. Returns whether there was one.
+    fn remove_stone(&mut self, coords: Coords) -> bool {
+        if !self.analysis_mode {
+            return false;
+        }
+        match self.game.game.board.get_color(coords) {
+            Some(removed) => {
+                // The next left click on this cell places the other color, which swaps the stone.
+                self.game = self.history.remove(coords);
+                self.game.game.current_player = board::opponent(removed);
+                self.forget_last_moves();
+                self.cancel_ai_move();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Plays `coords` for the side to move and records it. Returns `false` if the move was rejected.
+    fn play(&mut self, coords: Coords) -> bool {
+        let color = self.game.game.current_player;
+        let played = self.game.play(coords).is_ok();
+        if played {
+            self.history.record(coords, color);
+        }
+        played
+    }
+
+    /// Starts rating the human's move `coords` from the position `before` in the background. See
+    /// [`Self::poll_blunder_checks`] for how the result is applied.
+    fn start_blunder_check(&mut self, before: MctsHexGame, coords: Coords) {
+        let number = self.history.all_moves().count();
+        let after = self.game.clone();
+        let mut rng = SmallRng::seed_from_u64(rand::Rng::gen(&mut self.analysis_rng));
+        let receiver = spawn_search(move || {
+            // There is nothing to rate once the move has won.
+            if after.get_winner().is_some() {
+                return None;
+            }
+            let config = blunder_check_config();
+            let before = ai::evaluate(&before, &config, &mut rng)?;
+            // The position is now evaluated from the opponent's point of view.
+            let after = 1.0 - ai::evaluate(&after, &config, &mut rng)?;
+            Some(BlunderCheck { before, after })
+        });
+        self.blunder_checks.push((number, coords, receiver));
+    }
+
+    /// Rates the moves whose blunder checks have finished, and flags them as blunders if the mover's
+    /// win probability dropped too much. A move that has been undone in the meantime is not rated.
+    fn poll_blunder_checks(&mut self) {
+        let mut finished = Vec::new();
+        self.blunder_checks
+            .retain(|(number, coords, receiver)| match receiver.try_recv() {
+                Ok(check) => {
+                    finished.extend(check.map(|check| (*number, *coords, check)));
+                    false
+                }
+                Err(TryRecvError::Empty) => true,
+                Err(TryRecvError::Disconnected) => false,
+            });
+        for (number, coords, check) in finished {
+            if let Some(rated) = self.history.move_mut(number).filter(|m| m.coords == coords) {
+                rated.eval = Some(check.after);
+                let drop = check.before - check.after;
+                if drop > BLUNDER_THRESHOLD {
+                    rated.blunder = Some(drop);
+                }
+            }
+        }
+    }
+
+    /// Lets the AI choose its move for the side to move at input time `now`.
+    fn play_ai_reply(&mut self, now: f64) {
+        let ai_color = self.game.game.current_player;
+        let config = match self.controller(ai_color) {
+            Controller::Ai(config) => config,
+            Controller::Human => return,
+        };
+
+        if let (Opponent::Mirror, Some(human_move)) = (self.opponent, self.history.last()) {
+            let mirrored = ai::mirror_move(human_move.coords, self.game.game.board.size());
+            if self.game.game.board.get_color(mirrored).is_none() {
+                self.last_search = None;
+                self.hold_ai_move(mirrored, false, now);
+                return;
+            }
+        }
+
+        let swap_offered = self.can_swap();
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            // Pondering does not consider swapping.
+            if !swap_offered {
+                match self.take_pondered_reply() {
+                    Pondered::Reply(pondered) => {
+                        self.finish_ai_reply(AiReply::Move(pondered), now);
+                        return;
+                    }
+                    Pondered::Pending => {
+                        // Asks again once the next frame polls, without blocking this one.
+                        self.ai_move_due = Some(now + AI_SEARCH_POLL_INTERVAL);
+                        return;
+                    }
+                    Pondered::Missed => {}
+                }
+            }
+            // Searches on a clone, so the board keeps showing the position before the AI's move.
+            let game = self.game.clone();
+            let mut rng = self.ai_search_rng();
+            let (sender, receiver) = mpsc::channel();
+            std::thread::spawn(move || {
+                // Fails if the search was cancelled in the meantime.
+                sender
+                    .send(ai::reply(&game, &config, &mut rng, swap_offered))
+                    .ok();
+            });
+            self.ai_search = Some((board::position_key(&self.game), receiver));
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            match ai::reply(&self.game, &config, &mut self.rng, swap_offered) {
+                Some(reply) => self.finish_ai_reply(reply, now),
+                None => self.ai_failed = Some(board::position_key(&self.game)),
+            }
+        }
+    }
+
+    /// Plays or holds the move the AI's search suggested at input time `now`, or resigns.
+    fn finish_ai_reply(&mut self, reply: AiReply, now: f64) {
+        let ai_color = self.game.game.current_player;
+        let suggestion = match reply {
+            AiReply::Move(suggestion) => suggestion,
+            AiReply::Swap => {
+                self.swap();
+                return;
+            }
+        };
+        let resigns = ai::should_resign(&suggestion, self.ai_resign_threshold);
+        let forced = suggestion.is_forced();
+        // Taken first, so the move's rating is recorded when it is played right away.
+        let action = self.take_move(suggestion);
+        if resigns {
+            self.game.game.status = Status::Finished(board::opponent(ai_color));
+            self.resigned = Some(ai_color);
+        } else {
+            self.hold_ai_move(action, forced, now);
+        }
+    }
+
+    /// Commits the AI's move chosen at input time `now`. With `instant_forced_moves`, the AI
+    /// searched without waiting, so unless the move is `forced` it is held back for the AI delay.
+    fn hold_ai_move(&mut self, coords: Coords, forced: bool, now: f64) {
+        if self.instant_forced_moves && !forced {
+            self.held_ai_move = Some((coords, now + self.effective_ai_delay() as f64));
+        } else {
+            self.commit_ai_move(coords, now);
+        }
+    }
+
+    /// Plays the move the AI has chosen at input time `now`, or starts previewing it if
+    /// `ai_preview_duration` is set.
+    fn commit_ai_move(&mut self, coords: Coords, now: f64) {
+        if self.ai_preview_duration > 0.0 {
+            self.ai_preview = Some((coords, now + self.ai_preview_duration as f64));
+        } else {
+            self.play_ai_move(coords);
+        }
+    }
+
+    fn play_ai_move(&mut self, coords: Coords) {
+        if !self.play(coords) {
+            panic!("Failed to play AI move");
+        }
+        self.last_ai_move = Some(coords);
+        if let Some(search) = self
+            .last_search
+            .as_ref()
+            .filter(|search| search.action == coords)
+        {
+            self.history.set_last_eval(search.win_probability);
+        }
+    }
+
+    /// Takes back the last move. Outside analysis mode the AI's reply is taken back as well, so it is
+    /// the human's turn again.
+    fn undo(&mut self) {
+        if let Some(game) = self.history.undo() {
+            self.game = game;
+        }
+        if !self.is_human_turn() {
+            if let Some(game) = self.history.undo() {
+                self.game = game;
+            }
+        }
+        self.resigned = None;
+        self.cancel_ai_move();
+        self.last_search = None;
+        self.forget_last_moves();
+        self.rewind = None;
+    }
+
+    /// Continues the game from the position after the first `len` retained moves, discarding the
+    /// moves after it.
+    fn rewind_to(&mut self, len: usize) {
+        self.game = self.history.truncate(len);
+        self.resigned = None;
+        self.cancel_ai_move();
+        self.last_search = None;
+        self.forget_last_moves();
+        self.rewind = None;
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.game_logged = false;
+        }
+        self.difficulty_adjusted = false;
+        self.recent_game_recorded = false;
+    }
+
+    /// Repaints `delay` seconds after input time `now`, or after `idle_repaint_interval` if that is
+    /// shorter, without repainting in between.
+    ///
+    /// egui repaints on input or when asked to, but cannot be asked to repaint later. On native, a
+    /// thread asks for the repaint when it is due. In the browser, this repaints continuously.
+    fn request_repaint_after(
+        &mut self,
+        ctx: &egui::CtxRef,
+        frame: &epi::Frame,
+        now: f64,
+        delay: f64,
+    ) {
+        if self.idle_repaint_interval <= 0.0 {
+            ctx.request_repaint();
+            return;
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let _ = (frame, now, delay);
+            ctx.request_repaint();
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let delay = delay.clamp(0.0, self.idle_repaint_interval as f64);
+            let wake_up = now + delay;
+            if matches!(self.wake_up, Some(pending) if pending > now && pending <= wake_up) {
+                return;
+            }
+            self.wake_up = Some(wake_up);
+            let frame = frame.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_secs_f64(delay));
+                frame.request_repaint();
+            });
+        }
+    }
+
+    /// Plays the moves taken back by the last [`Self::undo`] again.
+    fn redo(&mut self) {
+        if let Some(game) = self.history.redo() {
+            self.game = game;
+        }
+        if !self.is_human_turn() && self.history.can_redo() {
+            if let Some(game) = self.history.redo() {
+                self.game = game;
+            }
+        }
+        self.cancel_ai_move();
+        self.last_search = None;
+        self.forget_last_moves();
+        self.rewind = None;
+    }
+
+    /// Whether the side to move may swap, i.e. the swap rule is on and exactly one stone has been
+    /// played. A swap is only ever offered on the second move.
+    fn can_swap(&self) -> bool {
+        let ongoing = matches!(self.game.game.status, Status::Ongoing);
+        let mut moves = self.history.all_moves();
+        let first_move_only =
+            matches!((moves.next(), moves.next()), (Some(first), None) if !first.swap);
+        self.swap_rule && ongoing && first_move_only
+    }
+
+    /// Swaps sides for the side to move, see [`board::swap_sides`].
+    fn swap(&mut self) {
+        let first = match self.history.last() {
+            Some(first) if self.can_swap() => first,
+            _ => return,
+        };
+        let (x, y) = board::to_xy(first.coords);
+        let color = self.game.game.current_player;
+        let human = self.is_human_turn();
+        self.game = board::swap_sides(&self.game);
+        self.history.record_swap(Coords::new(y, x), color);
+        // The swapped stone now counts as the swapping side's move.
+        self.forget_last_moves();
+        if human {
+            self.last_human_move = Some(Coords::new(y, x));
+        } else {
+            self.last_ai_move = Some(Coords::new(y, x));
+        }
+        self.cancel_ai_move();
+        self.last_search = None;
+        self.hint = None;
+    }
+
+    /// Gives up the game for the side to move.
+    fn resign(&mut self) {
+        let loser = self.game.game.current_player;
+        self.game.game.status = Status::Finished(board::opponent(loser));
+        self.resigned = Some(loser);
+        self.cancel_ai_move();
+    }
+
+    /// Searches the current position in the background and marks the move the AI would play on the
+    /// board, see [`Self::best_move`].
+    fn show_hint(&mut self) {
+        let position = board::position_hash(&self.game.game.board);
+        if let Some(drill) = &self.drill {
+            // Reveals the opening's next move rather than the AI's choice.
+            if let Some(expected) = drill.expected(self.history.all_moves().count()) {
+                self.hint = Some((expected, position));
+                return;
+            }
+        }
+        self.start_search(SearchPurpose::Hint, self.ai_config());
+    }
+
+    /// Lets the AI move for the side to move right away, even if a human plays it. The move is
+    /// played once its background search has finished, see [`Self::best_move`].
+    fn force_ai_move(&mut self, now: f64) {
+        if self.ai_move_due.is_some() {
+            // The AI is waiting to make its own move, skip the rest of the delay.
+            self.ai_move_due = Some(now);
+            return;
+        }
+        self.start_search(SearchPurpose::ForcedMove, self.ai_config());
+    }
+
+    /// Which of the game actions in the side panel can be used right now.
+    fn button_states(&self) -> ButtonStates {
+        let ongoing = matches!(self.game.game.status, Status::Ongoing);
+        let ai_busy = self.ai_thinking()
+            || self.held_ai_move.is_some()
+            || self.ai_preview.is_some()
+            || self.search_job.is_some();
+        let size_changed = self.configured_size != self.game.game.board.size();
+        ButtonStates {
+            reset: size_changed || !self.is_untouched(),
+            undo: !self.history.is_empty(),
+            redo: self.history.can_redo(),
+            resign: ongoing && !self.analysis_mode && self.drill.is_none() && self.is_human_turn(),
+            hint: ongoing && !ai_busy && self.is_human_turn(),
+            swap: !ai_busy && self.is_human_turn() && self.can_swap(),
+            force_ai_move: ongoing && !ai_busy,
+        }
+    }
+
+    fn effective_ai_delay(&self) -> f32 {
+        if self.automatic_ai_delay {
+            default_ai_delay(self.game.game.board.size())
+        } else {
+            self.ai_delay
+        }
+    }
+
+    /// The generator for one search of the AI on a background thread, drawn from `rng` so that
+    /// every search gets a different one.
+    fn ai_search_rng(&mut self) -> SmallRng {
+        SmallRng::seed_from_u64(rand::Rng::gen(&mut self.rng))
+    }
+
+    /// Starts a new random sequence for the AI, reproducible from `seed` in deterministic mode.
+    fn reset_rng(&mut self) {
+        if self.deterministic {
+            self.rng = SmallRng::seed_from_u64(self.seed);
+            self.analysis_rng = SmallRng::seed_from_u64(board::splitmix64(self.seed));
+        } else {
+            self.rng = SmallRng::from_entropy();
+            self.analysis_rng = SmallRng::from_entropy();
+        }
+    }
+
+    /// Older versions allowed a size of 0, which cannot be played. Repair such state after loading it.
+    #[cfg(feature = "persistence")]
+    fn sanitize_restored_state(&mut self) {
+        self.configured_size = self.configured_size.max(MIN_BOARD_SIZE);
+        if self.game.game.board.size() < MIN_BOARD_SIZE {
+            self.new_game(DEFAULT_BOARD_SIZE);
+        }
+        self.restore_saved_game();
+    }
+
+    /// Remembers the game in progress for [`Self::restore_saved_game`]. Finished games are kept in
+    /// the recent games instead, and edited positions, see [`Self::game_text_is_exact`], are not
+    /// kept.
+    #[cfg(feature = "persistence")]
+    fn save_game_in_progress(&mut self) {
+        let ongoing = matches!(self.game.game.status, Status::Ongoing);
+        let played = self.history.all_moves().next().is_some();
+        self.saved_game = if ongoing && played && self.game_text_is_exact() {
+            Some(self.game_text())
+        } else {
+            None
+        };
+    }
+
+    /// Replays the game saved by [`Self::save_game_in_progress`]. A saved game that no longer
+    /// replays, e.g. because it is on a board size that is not supported anymore, is dropped for an
+    /// empty board.
+    #[cfg(feature = "persistence")]
+    fn restore_saved_game(&mut self) {
+        if let Some(text) = self.saved_game.take() {
+            let size = self.configured_size;
+            if self.load_game_text(&text).is_err() {
+                self.new_game(size);
+            }
+        }
+    }
+}
+
+/// The outcome of [`HexGameUi::take_pondered_reply`].
+#[cfg(not(target_arch = "wasm32"))]
+enum Pondered {
+    Reply(Suggestion),
+    /// The pondering has not finished yet.
+    Pending,
+    /// There was no pondering, or it predicted another position.
+    Missed,
+}
+
+/// What a click on the board widget does when it does not hit a cell, i.e. in the gaps between
+/// the cells or around the board.
+///
+/// Such a click never places a stone. Neither does a drag, even with drag to place, unless it is
+/// released on a cell.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "persistence", derive(serde::Deserialize, serde::Serialize))]
+enum OffBoardClick {
+    Ignore,
+    /// Remove the outline of the move suggested by "Hint".
+    ClearHint,
+}
+
+impl OffBoardClick {
+    fn name(self) -> &'static str {
+        match self {
+            OffBoardClick::Ignore => "Ignore",
+            OffBoardClick::ClearHint => "Clear the hint",
+        }
+    }
+}
+
+/// The light or dark look the user asked for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "persistence", derive(serde::Deserialize, serde::Serialize))]
+enum ThemePreference {
+    /// Follow the operating system's preference where it can be detected, otherwise dark.
+    System,
+    Dark,
+    Light,
+}
+
+impl ThemePreference {
+    fn name(self) -> &'static str {
+        match self {
+            ThemePreference::System => "System",
+            ThemePreference::Dark => "Dark",
+            ThemePreference::Light => "Light",
+        }
+    }
+
+    fn visuals(self) -> Visuals {
+        match self {
+            ThemePreference::System if system_prefers_light() => Visuals::light(),
+            ThemePreference::System | ThemePreference::Dark => Visuals::dark(),
+            ThemePreference::Light => Visuals::light(),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn system_prefers_light() -> bool {
+    matches!(dark_light::detect(), dark_light::Mode::Light)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn system_prefers_light() -> bool {
+    false
+}
+
+/// The current time in seconds since the Unix epoch. Unknown on the web, where the standard library
+/// has no clock.
+#[cfg(not(target_arch = "wasm32"))]
+fn unix_time() -> Option<u64> {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()
+        .map(|duration| duration.as_secs())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn unix_time() -> Option<u64> {
+    None
+}
+
+/// Range of the AI iterations slider. Adaptive difficulty has its own limits, see
+/// [`MIN_AI_ITERATIONS`].
+const MIN_SLIDER_ITERATIONS: u32 = 10;
+const MAX_SLIDER_ITERATIONS: u32 = 50_000;
+
+/// Seconds between checks whether a background search has finished.
+const AI_SEARCH_POLL_INTERVAL: f64 = 0.05;
+
+const DEFAULT_BOARD_SIZE: u8 = 5;
+
+/// The AI is nearly instant on small boards, where a stone that appears right away is easy to miss.
+/// Wait a little longer there, while larger boards already take a while to search.
+fn default_ai_delay(size: u8) -> f32 {
+    (0.7 - 0.05 * size as f32).clamp(0.1, 0.6)
+}
+const DEFAULT_UNDO_STEPS: usize = 50;
+
+/// Limits for the iteration count chosen by adaptive difficulty.
+const MIN_AI_ITERATIONS: u32 = 100;
+const MAX_AI_ITERATIONS: u32 = 200_000;
+
+/// Range of the AI's time per move in seconds, for a time budget.
+const MIN_AI_TIME_BUDGET: f32 = 0.1;
+const MAX_AI_TIME_BUDGET: f32 = 5.0;
+#[cfg(not(target_arch = "wasm32"))]
+const DEFAULT_AI_TIME_BUDGET: f32 = 1.0;
+/// How much adaptive difficulty changes a time budget after a game, in seconds.
+const DIFFICULTY_TIME_STEP: f32 = 0.1;
+
+/// A human move is flagged as a blunder if it lowers the mover's win probability by more than this.
+const BLUNDER_THRESHOLD: f32 = 0.2;
+
+/// What a search started from the side panel or the engine console is for, see
+/// [`HexGameUi::start_search`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SearchPurpose {
+    /// Marks the move on the board, see [`HexGameUi::show_hint`].
+    Hint,
+    /// Plays the move, see [`HexGameUi::force_ai_move`].
+    ForcedMove,
+    /// `go`, which reports the best move.
+    ConsoleGo,
+    /// `eval`, which reports the win probability of the side to move.
+    ConsoleEval,
+}
+
+/// The mover's estimated win probability before and after a move, see
+/// [`HexGameUi::start_blunder_check`].
+struct BlunderCheck {
+    before: f32,
+    after: f32,
+}
+
+/// Runs `search` on a background thread and returns where its result arrives, so the frame is not
+/// held up. Web builds have no threads, there `search` runs right away and its result is ready on
+/// return.
+fn spawn_search<T: Send + 'static>(search: impl FnOnce() -> T + Send + 'static) -> Receiver<T> {
+    let (sender, receiver) = mpsc::channel();
+    #[cfg(not(target_arch = "wasm32"))]
+    std::thread::spawn(move || {
+        // Fails if the result is not wanted anymore.
+        sender.send(search()).ok();
+    });
+    #[cfg(target_arch = "wasm32")]
+    sender.send(search()).ok();
+    receiver
+}
+
+/// Blunder detection runs two extra searches per move, so it uses a cheaper search than the AI.
+fn blunder_check_config() -> AiConfig {
+    AiConfig {
+        iterations: 1_000,
+        ..AiConfig::default()
+    }
+}
+
+/// Number of games behind an estimate of the first move advantage.
+const SELF_PLAY_GAMES: u32 = 20;
+
+/// Estimating the first move advantage plays whole games, so it uses a much cheaper search than the AI.
+fn self_play_config() -> AiConfig {
+    AiConfig {
+        iterations: 200,
+        ..AiConfig::default()
+    }
+}
+
+/// How quickly the hover highlight follows the pointer, in fractions of the remaining distance per second.
+const HOVER_ANIMATION_SPEED: f32 = 25.0;
+/// Length of the bright pulse flowing along the winning path, in cells. The pulse also pauses for
+/// this long after reaching the far edge.
+const VICTORY_PULSE_LENGTH: f32 = 3.0;
+/// Seconds a cell flashes after a click on it was rejected.
+const REJECTED_CLICK_FLASH: f64 = 0.4;
+/// Seconds after a move played by a click during which further clicks are ignored. A slow frame,
+/// e.g. one that ran a search, can otherwise turn one click into two moves.
+const CLICK_DEBOUNCE: f64 = 0.1;
+/// Distance of the edge labels from the goal edges, in points.
+const EDGE_LABEL_DISTANCE: f32 = 12.0;
+/// Distance of the coordinate labels from the goal edges, in points. The edge labels move outwards
+/// by [`COORDINATE_LABEL_SPACE`] to make room for them.
+const COORDINATE_LABEL_DISTANCE: f32 = 8.0;
+const COORDINATE_LABEL_SPACE: f32 = 16.0;
+const TOGGLE_SIDE_PANEL_HINT: &str = "Toggle the side panel (Ctrl+B)";
+
+impl epi::App for HexGameUi {
+    fn name(&self) -> &str {
+        "eframe template"
+    }
+
+    /// Called once before the first frame.
+    fn setup(
+        &mut self,
+        ctx: &egui::CtxRef,
+        _frame: &epi::Frame,
+        _storage: Option<&dyn epi::Storage>,
+    ) {
+        // Load previous app state (if any).
+        // Note that you must enable the `persistence` feature for this to work.
+        #[cfg(feature = "persistence")]
+        if let Some(storage) = _storage {
+            *self = epi::get_value(storage, epi::APP_KEY).unwrap_or_default();
+            self.sanitize_restored_state();
+        }
+        self.reset_rng();
+
+        #[cfg(target_arch = "wasm32")]
+        if let Some(shared) = crate::web::read_shared_game() {
+            // A broken link should not keep the app from starting, so just ignore it.
+            self.load_game_text(&shared).ok();
+        }
+
+        ctx.set_visuals(self.theme.visuals());
+    }
+
+    /// Called by the frame work to save state before shutdown.
+    /// Note that you must enable the `persistence` feature for this to work.
+    #[cfg(feature = "persistence")]
+    fn save(&mut self, storage: &mut dyn epi::Storage) {
+        self.save_game_in_progress();
+        epi::set_value(storage, epi::APP_KEY, self);
+    }
+
+    /// Called each time the UI needs repainting, which may be many times per second.
+    /// Put your widgets into a `SidePanel`, `TopPanel`, `CentralPanel`, `Window` or `Area`.
+    fn update(&mut self, ctx: &egui::CtxRef, frame: &epi::Frame) {
+        #[cfg(target_arch = "wasm32")]
+        self.update_shared_url();
+        #[cfg(not(target_arch = "wasm32"))]
+        self.log_finished_game();
+        self.adjust_difficulty();
+        self.record_recent_game();
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.update_oracle(frame);
+            self.update_ponder(frame);
+        }
+
+        let input = ctx.input();
+        if input.modifiers.command && input.key_pressed(egui::Key::B) {
+            self.show_side_panel = !self.show_side_panel;
+        }
+        self.size_slider_active = false;
+
+        if let Some(delay) = self.advance_ai(input.time) {
+            self.request_repaint_after(ctx, frame, input.time, delay);
+        }
+        self.poll_blunder_checks();
+        self.poll_advantage_job();
+        self.poll_search_job();
+        if self.ai_thinking() || self.searching_in_background() {
+            // Polls the search, which has no way to wake up the window itself.
+            self.request_repaint_after(ctx, frame, input.time, AI_SEARCH_POLL_INTERVAL);
+        }
+        if self.ai_preview.is_some() {
+            // Keeps the preview flashing.
+            ctx.request_repaint();
+        }
+
+        // A pending AI move was chosen with the old settings, or for a side a human took over.
+        let controllers = self.controllers;
+        egui::SidePanel::left("side_panel").show_animated(ctx, self.show_side_panel, |ui| {
+            ui.horizontal(|ui| {
+                ui.heading("Options");
+                if ui.small_button("⏴").on_hover_text(TOGGLE_SIDE_PANEL_HINT).clicked() {
+                    self.show_side_panel = false;
+                }
+            });
+            self.draw_game_status(ui);
+
+            ui.horizontal(|ui| {
+                ui.label("Field Size: ");
+                let slider = ui
+                    .add(Slider::new(&mut self.configured_size, MIN_BOARD_SIZE..=MAX_BOARD_SIZE))
+                    .on_hover_text("Applies right away while the board is empty, otherwise when the game is reset.");
+                if slider.changed() && self.is_untouched() {
+                    self.reset_game();
+                }
+                self.size_slider_active = slider.hovered() || slider.has_focus() || slider.dragged();
+            });
+
+            let buttons = self.button_states();
+            ui.horizontal_wrapped(|ui| {
+                if ui
+                    .add_enabled(buttons.reset, egui::Button::new("Reset game..."))
+                    .clicked()
+                {
+                    self.reset_game();
+                }
+                if ui.add_enabled(buttons.undo, egui::Button::new("Undo")).clicked() {
+                    self.undo();
+                }
+                if ui.add_enabled(buttons.redo, egui::Button::new("Redo")).clicked() {
+                    self.redo();
+                }
+                if self.swap_rule
+                    && ui
+                        .add_enabled(buttons.swap, egui::Button::new("Swap"))
+                        .on_hover_text("Take over the first stone instead of answering it.")
+                        .clicked()
+                {
+                    self.swap();
+                }
+                if ui
+                    .add_enabled(buttons.resign, egui::Button::new("Resign"))
+                    .clicked()
+                {
+                    self.resign();
+                }
+                if ui
+                    .add_enabled(buttons.hint, egui::Button::new("Hint"))
+                    .on_hover_text("Mark the move the AI would play.")
+                    .clicked()
+                {
+                    self.show_hint();
+                }
+                if ui
+                    .add_enabled(buttons.force_ai_move, egui::Button::new("AI move"))
+                    .on_hover_text("Let the AI play the side to move now.")
+                    .clicked()
+                {
+                    let now = ui.input().time;
+                    self.force_ai_move(now);
+                }
+            });
+            if self.ai_gave_up() {
+                ui.colored_label(
+                    Color32::RED,
+                    "The AI's search failed in this position. Undo or edit the position to continue.",
+                );
+            }
+
+            let openings = self.openings.get(&self.configured_size);
+            let mut opening = self.opening.clone();
+            egui::ComboBox::from_label("Opening")
+                .selected_text(opening.as_deref().unwrap_or("None"))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut opening, None, "None");
+                    for (name, moves) in openings.into_iter().flatten() {
+                        let key = drill::key(self.configured_size, name);
+                        let label = if self.completed_drills.contains(&key) {
+                            format!("{} (practiced)", name)
+                        } else {
+                            name.clone()
+                        };
+                        ui.selectable_value(&mut opening, Some(name.clone()), label)
+                            .on_hover_text(notation::format_moves(moves, self.coordinate_system));
+                    }
+                });
+            if opening != self.opening {
+                self.drill = None;
+                let untouched = self.is_untouched();
+                self.opening = opening;
+                if untouched {
+                    self.reset_game();
+                }
+            }
+
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(self.opening.is_some(), egui::Button::new("Practice"))
+                    .on_hover_text("Play the chosen opening from memory, move by move.")
+                    .clicked()
+                {
+                    if let Some(name) = self.opening.clone() {
+                        self.start_drill(self.configured_size, name);
+                    }
+                }
+                if self.drill.is_some() && ui.button("Stop practicing").clicked() {
+                    self.drill = None;
+                }
+            });
+            self.draw_drill(ui);
+
+            if self.history.is_empty() {
+                self.draw_first_move_advantage(ui);
+            }
+
+            ui.checkbox(&mut self.swap_rule, "Swap rule")
+                .on_hover_text("After the first stone, the second player may take it over instead of answering it.");
+
+            egui::ComboBox::from_label("Opponent")
+                .selected_text(self.opponent.name())
+                .show_ui(ui, |ui| {
+                    for opponent in [Opponent::Mcts, Opponent::Mirror] {
+                        ui.selectable_value(&mut self.opponent, opponent, opponent.name());
+                    }
+                });
+
+            let theme = self.board_theme();
+            for color in [Color::Black, Color::White] {
+                let index = board::color_index(color);
+                ui.horizontal(|ui| {
+                    ui.colored_label(
+                        theme.player(color),
+                        format!("{}: ", board::color_name(color)),
+                    );
+                    let name = match color {
+                        Color::Black => &mut self.red_name,
+                        Color::White => &mut self.blue_name,
+                    };
+                    ui.add(egui::TextEdit::singleline(name).desired_width(80.0));
+
+                    let controller = &mut self.controllers[index];
+                    egui::ComboBox::from_id_source(("controller", index))
+                        .selected_text(controller.name())
+                        .show_ui(ui, |ui| {
+                            let is_human = *controller == Controller::Human;
+                            if ui.selectable_label(is_human, "Human").clicked() {
+                                *controller = Controller::Human;
+                            }
+                            if ui.selectable_label(!is_human, "AI").clicked() && is_human {
+                                *controller = Controller::Ai(AiConfig::default());
+                            }
+                        });
+                });
+
+                if let Controller::Ai(config) = &mut self.controllers[index] {
+                    egui::ComboBox::from_label(format!(
+                        "{} AI personality",
+                        board::color_name(color)
+                    ))
+                    .selected_text(config.personality.name())
+                    .show_ui(ui, |ui| {
+                        for personality in [
+                            Personality::Balanced,
+                            Personality::Aggressive,
+                            Personality::Defensive,
+                        ] {
+                            ui.selectable_value(
+                                &mut config.personality,
+                                personality,
+                                personality.name(),
+                            )
+                            .on_hover_text("The search tries the moves that suit the personality first, and the AI favors them in its simulated games. It still plays the move that held up best in the search.");
+                        }
+                    });
+                    #[cfg(not(target_arch = "wasm32"))]
+                    {
+                        let mut timed = matches!(config.budget, SearchBudget::Time(_));
+                        if ui
+                            .checkbox(&mut timed, format!("{} AI thinks for a fixed time", board::color_name(color)))
+                            .on_hover_text("Instead of a fixed number of iterations. The AI's moves are then not reproducible, even in deterministic mode.")
+                            .changed()
+                        {
+                            config.budget = if timed {
+                                SearchBudget::Time(DEFAULT_AI_TIME_BUDGET)
+                            } else {
+                                SearchBudget::Iterations
+                            };
+                        }
+                    }
+                    match &mut config.budget {
+                        SearchBudget::Time(seconds) => {
+                            ui.add(
+                                Slider::new(seconds, MIN_AI_TIME_BUDGET..=MAX_AI_TIME_BUDGET)
+                                    .logarithmic(true)
+                                    .suffix(" s")
+                                    .text(format!("{} AI time per move", board::color_name(color))),
+                            )
+                            .on_hover_text(if self.adaptive_difficulty {
+                                "More time makes the AI stronger. Adaptive difficulty changes this after every game."
+                            } else {
+                                "More time makes the AI stronger."
+                            });
+                        }
+                        SearchBudget::Iterations => {
+                            ui.add(
+                                Slider::new(&mut config.iterations, MIN_SLIDER_ITERATIONS..=MAX_SLIDER_ITERATIONS)
+                                    .logarithmic(true)
+                                    .text(format!("{} AI iterations", board::color_name(color))),
+                            )
+                            .on_hover_text(if self.adaptive_difficulty {
+                                "More iterations make the AI stronger and slower. Adaptive difficulty changes this after every game."
+                            } else {
+                                "More iterations make the AI stronger and slower."
+                            });
+                        }
+                    }
+                }
+            }
+            let mut watching = self.watching.is_some();
+            if ui
+                .checkbox(&mut watching, "Watch AI vs AI")
+                .on_hover_text("The AI plays both sides. Turn this off to take over your sides again.")
+                .changed()
+            {
+                self.set_watching(watching);
+            }
+
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.adaptive_difficulty, "Adaptive difficulty, step: ")
+                    .on_hover_text("The AI searches longer after you win and shorter after you lose.");
+                ui.add_enabled(
+                    self.adaptive_difficulty,
+                    egui::DragValue::new(&mut self.difficulty_step).clamp_range(100..=50_000),
+                );
+            });
+
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.deterministic, "Deterministic AI, seed: ")
+                    .on_hover_text("Takes effect when the next game starts.");
+                ui.add_enabled(self.deterministic, egui::DragValue::new(&mut self.seed));
+            });
+
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.automatic_ai_delay, "Automatic AI delay");
+                if self.automatic_ai_delay {
+                    self.ai_delay = default_ai_delay(self.game.game.board.size());
+                }
+                ui.add_enabled(
+                    !self.automatic_ai_delay,
+                    Slider::new(&mut self.ai_delay, 0.0..=2.0).suffix(" s"),
+                );
+            });
+
+            ui.add(
+                Slider::new(&mut self.ai_preview_duration, 0.0..=2.0)
+                    .suffix(" s")
+                    .text("AI move preview"),
+            )
+            .on_hover_text("Flash the AI's chosen cell for this long before its stone is placed.");
+            ui.checkbox(&mut self.auto_start_ai, "AI starts by itself")
+                .on_hover_text("When the AI moves first, it opens the game without waiting for \"AI move\".");
+            ui.checkbox(&mut self.instant_forced_moves, "Play forced moves instantly")
+                .on_hover_text("The AI skips its delay when nearly all of its search went into one move.");
+            #[cfg(not(target_arch = "wasm32"))]
+            ui.checkbox(&mut self.ponder, "Ponder")
+                .on_hover_text("The AI guesses your move and prepares its reply while you think.");
+
+            ui.horizontal(|ui| {
+                ui.label("AI resigns below: ");
+                ui.add(Slider::new(&mut self.ai_resign_threshold, 0.0..=0.5))
+                    .on_hover_text("Estimated win probability at which the AI gives up. 0 disables resigning.");
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Board margin: ");
+                ui.add(Slider::new(&mut self.board_margin, 0.0..=100.0));
+            });
+            ui.horizontal(|ui| {
+                ui.label("Cell gap: ");
+                ui.add(Slider::new(&mut self.cell_gap, 0.0..=0.3))
+                    .on_hover_text("Space between neighboring cells, from touching to spread out.");
+            });
+            ui.add(Slider::new(&mut self.min_cell_size, 10.0..=60.0).text("smallest cell size"))
+                .on_hover_text("Below this size, the board scrolls instead of shrinking further.");
+            ui.add(Slider::new(&mut self.max_cell_size, 20.0..=200.0).text("largest cell size"));
+            self.max_cell_size = self.max_cell_size.max(self.min_cell_size);
+
+            if ui
+                .checkbox(&mut self.analysis_mode, "Analysis mode")
+                .on_hover_text("Left click places a stone for the side to move, right click removes a stone. The AI does not reply.")
+                .changed()
+            {
+                self.cancel_ai_move();
+            }
+            if self.analysis_mode {
+                let to_move_before = self.game.game.current_player;
+                ui.horizontal(|ui| {
+                    let to_move = &mut self.game.game.current_player;
+                    egui::ComboBox::from_id_source("side_to_move")
+                        .selected_text(format!("{} to move", board::color_name(*to_move)))
+                        .show_ui(ui, |ui| {
+                            for color in [Color::Black, Color::White] {
+                                ui.selectable_value(
+                                    to_move,
+                                    color,
+                                    format!("{} to move", board::color_name(color)),
+                                );
+                            }
+                        });
+                    if ui
+                        .button("Play from here")
+                        .on_hover_text("Continue this position with the players chosen above.")
+                        .clicked()
+                    {
+                        self.play_from_here_error = self.play_from_here().err();
+                    }
+                });
+                if self.game.game.current_player != to_move_before {
+                    self.cancel_ai_move();
+                }
+                if let Some(error) = &self.play_from_here_error {
+                    ui.colored_label(Color32::RED, error);
+                }
+            }
+
+            ui.collapsing("Moves", |ui| {
+                egui::ScrollArea::vertical()
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        let mut clicked = None;
+                        for (index, (number, m)) in self.history.numbered_moves().enumerate() {
+                            let mut text = format!(
+                                "{}. {} {}",
+                                number,
+                                self.player_name(m.color),
+                                notation::format_coord(m.coords, self.coordinate_system)
+                            );
+                            if m.swap {
+                                text.push_str(" (swap)");
+                            }
+                            if self.rewind == Some(index + 1) {
+                                text.push_str(" ◀");
+                            }
+                            let response = match m.blunder {
+                                Some(drop) => ui.colored_label(
+                                    Color32::YELLOW,
+                                    format!("{} ?? (-{:.0}%)", text, drop * 100.0),
+                                ),
+                                None => ui.label(text),
+                            };
+                            if response
+                                .interact(Sense::click())
+                                .on_hover_text("Show the position after this move.")
+                                .clicked()
+                            {
+                                clicked = Some(index + 1);
+                            }
+                        }
+                        if let Some(len) = clicked {
+                            // The last move leads to the current position, there is nothing to show.
+                            self.rewind = Some(len).filter(|&len| len < self.history.len());
+                        }
+                    });
+
+                if let Some(len) = self.rewind {
+                    let discarded = self.history.len() - len;
+                    ui.label(format!(
+                        "Continuing from here discards the {} later move{}.",
+                        discarded,
+                        if discarded == 1 { "" } else { "s" }
+                    ));
+                    ui.horizontal(|ui| {
+                        if ui.button("Continue from here").clicked() {
+                            self.rewind_to(len);
+                        }
+                        if ui.button("Back to the game").clicked() {
+                            self.rewind = None;
+                        }
+                    });
+                }
+            });
+
+            ui.collapsing("Analysis", |ui| {
+                self.draw_evaluation_bar(ui);
+                self.draw_connection_progress(ui);
+                ui.checkbox(&mut self.show_candidates, "Show candidates on the board");
+                ui.checkbox(&mut self.show_bridges, "Show bridges")
+                    .on_hover_text("Pairs of stones of the side to move that cannot be cut apart.");
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    let tiny = self.game.game.board.size() <= MAX_ORACLE_SIZE;
+                    ui.add_enabled(tiny, egui::Checkbox::new(&mut self.show_oracle, "Oracle"))
+                        .on_hover_text(format!(
+                            "Mark every empty cell green if it wins with perfect play and red if it loses. Only on boards up to {0}×{0}.",
+                            MAX_ORACLE_SIZE
+                        ));
+                    if self.show_oracle && self.oracle_job.is_some() {
+                        ui.label("Solving...");
+                    }
+                }
+                ui.checkbox(&mut self.detect_blunders, "Detect blunders")
+                    .on_hover_text("Runs two quick searches after every move of yours.");
+                match &self.last_search {
+                    Some(search) => {
+                        for (rank, candidate) in search.candidates.iter().enumerate() {
+                            ui.horizontal(|ui| {
+                                ui.label(format!(
+                                    "{}. {}: {} visits, {:.0}% win",
+                                    rank + 1,
+                                    notation::format_coord(candidate.action, self.coordinate_system),
+                                    candidate.visits,
+                                    candidate.win_probability * 100.0
+                                ));
+                                if ui
+                                    .small_button("Compare")
+                                    .on_hover_text("Show the position after this move next to the game.")
+                                    .clicked()
+                                {
+                                    let mut preview = self.game.clone();
+                                    if preview.play(candidate.action).is_ok() {
+                                        self.comparison = Some(preview);
+                                    }
+                                }
+                            });
+                        }
+                    }
+                    None => {
+                        ui.label("The AI has not searched this position yet.");
+                    }
+                }
+
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("Pin for comparison")
+                        .on_hover_text("Keep showing the current position next to the game.")
+                        .clicked()
+                    {
+                        self.comparison = Some(self.game.clone());
+                    }
+                    if ui
+                        .add_enabled(self.comparison.is_some(), egui::Button::new("Stop comparing"))
+                        .clicked()
+                    {
+                        self.comparison = None;
+                    }
+                });
+            });
+
+            let theme = self.board_theme();
+            ui.collapsing("Legend", |ui| {
+                for color in [Color::Black, Color::White] {
+                    let edges = match board::goal_axis(color) {
+                        Axis::X => "left and right",
+                        Axis::Y => "top and bottom",
+                    };
+                    ui.colored_label(
+                        theme.player(color),
+                        format!("{} connects the {} edges.", board::color_name(color), edges),
+                    );
+                }
+                ui.label("Corner cells touch two edges and count for both players.");
+            });
+
+            #[cfg(not(target_arch = "wasm32"))]
+            ui.collapsing("Game log", |ui| {
+                ui.checkbox(&mut self.log_games, "Append finished games to a file");
+                ui.add_enabled(
+                    self.log_games,
+                    egui::TextEdit::singleline(&mut self.game_log_path),
+                );
+                if let Some(error) = &self.game_log_error {
+                    ui.colored_label(Color32::RED, error);
+                }
+            });
+
+            ui.collapsing("Recent games", |ui| self.draw_recent_games(ui));
+
+            ui.collapsing("Export", |ui| {
+                ui.add(
+                    egui::TextEdit::multiline(&mut self.game_text_input)
+                        .hint_text("Board size and moves, e.g. 5 c3 d2 b4")
+                        .desired_rows(2),
+                );
+                ui.horizontal(|ui| {
+                    if ui.button("Copy game").clicked() {
+                        if self.game_text_is_exact() {
+                            self.game_text_input = self.game_text();
+                            ui.output().copied_text = self.game_text_input.clone();
+                            self.game_text_error = None;
+                        } else {
+                            self.game_text_error = Some(
+                                "The position was edited, so its moves do not reproduce it."
+                                    .to_owned(),
+                            );
+                        }
+                    }
+                    if ui.button("Load game").clicked() {
+                        let text = self.game_text_input.clone();
+                        self.game_text_error = self.load_game_text(&text).err();
+                    }
+                });
+                if let Some(error) = &self.game_text_error {
+                    ui.colored_label(Color32::RED, error);
+                }
+                ui.checkbox(&mut self.export_coordinates, "Include coordinates");
+                if ui
+                    .button("Export SVG")
+                    .on_hover_text("Copies the board as an SVG image to the clipboard.")
+                    .clicked()
+                {
+                    let options = SvgOptions {
+                        coordinates: Some(self.coordinate_system)
+                            .filter(|_| self.export_coordinates),
+                        title: self.game_id().map(|id| format!("Game ID {}", id)),
+                        ..SvgOptions::default()
+                    };
+                    ui.output().copied_text = svg::board_to_svg(&self.game.game.board, &options);
+                }
+                if ui
+                    .button("Export summary")
+                    .on_hover_text("Copies the players, moves, AI ratings and result as JSON to the clipboard.")
+                    .clicked()
+                {
+                    ui.output().copied_text = summary::summary_to_json(&self.game_summary());
+                }
+
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    ui.separator();
+                    ui.add(egui::TextEdit::singleline(&mut self.png_export_path));
+                    ui.add(Slider::new(&mut self.png_export_scale, 0.5..=8.0).text("pixels per point"));
+                    if ui.button("Export PNG").clicked() {
+                        self.png_export_error = crate::png_export::save_png(
+                            Path::new(&self.png_export_path),
+                            &self.game.game.board,
+                            self.png_export_scale,
+                            self.game_id()
+                                .map(|id| format!("Game ID {}", id))
+                                .as_deref(),
+                        )
+                        .err()
+                        .map(|error| format!("Could not write {}: {}", self.png_export_path, error));
+                    }
+                    if let Some(error) = &self.png_export_error {
+                        ui.colored_label(Color32::RED, error);
+                    }
+                }
+            });
+
+            ui.collapsing("Engine console", |ui| {
+                egui::ScrollArea::vertical()
+                    .max_height(150.0)
+                    .stick_to_bottom()
+                    .show(ui, |ui| {
+                        for line in &self.console_output {
+                            ui.monospace(line);
+                        }
+                    });
+                let input = ui.add(
+                    egui::TextEdit::singleline(&mut self.console_input)
+                        .code_editor()
+                        .hint_text("help"),
+                );
+                if input.lost_focus() && ui.input().key_pressed(egui::Key::Enter) {
+                    let line = std::mem::take(&mut self.console_input);
+                    if !line.trim().is_empty() {
+                        self.run_console_command(&line);
+                    }
+                    input.request_focus();
+                }
+            });
+
+            ui.collapsing("Advanced", |ui| {
+                ui.checkbox(&mut self.animations, "Animations");
+                ui.add_enabled(
+                    self.animations,
+                    Slider::new(&mut self.victory_flow_speed, 0.5..=10.0)
+                        .text("victory highlight speed"),
+                )
+                .on_hover_text("Cells per second the highlight of the winning connection travels.");
+                ui.checkbox(&mut self.show_debug_overlay, "Debug overlay");
+                ui.checkbox(&mut self.drag_to_place, "Drag to place")
+                    .on_hover_text("Press on the board, drag to a cell and release to place a stone there. Handy on trackpads.");
+                egui::ComboBox::from_label("Clicks next to the cells")
+                    .selected_text(self.off_board_click.name())
+                    .show_ui(ui, |ui| {
+                        for action in [OffBoardClick::Ignore, OffBoardClick::ClearHint] {
+                            ui.selectable_value(&mut self.off_board_click, action, action.name());
+                        }
+                    });
+                ui.checkbox(&mut self.show_size_overlay, "Outline the new size while changing it");
+                #[cfg(not(target_arch = "wasm32"))]
+                ui.add(
+                    Slider::new(&mut self.idle_repaint_interval, 0.0..=1.0)
+                        .suffix(" s")
+                        .text("idle repaint interval"),
+                )
+                .on_hover_text("While waiting for the AI, repaint at most this often to save power. 0 repaints continuously.");
+                // These apply to every AI player, starting from the settings of the one to move.
+                let mut config = self.ai_config();
+                let mut changed = ui
+                    .add(
+                        Slider::new(&mut config.max_nodes, 100_000..=50_000_000)
+                            .logarithmic(true)
+                            .text("max search nodes"),
+                    )
+                    .on_hover_text("The AI runs fewer iterations when its search tree would grow beyond this.")
+                    .changed();
+                changed |= ui
+                    .add(
+                        Slider::new(&mut config.exploration_parameter, 0.0..=2.0)
+                            .text("exploration"),
+                    )
+                    .on_hover_text("Higher values make the AI look at more moves, lower values make it look deeper at the best ones.")
+                    .changed();
+                let mut anneal = config.late_exploration_parameter.is_some();
+                changed |= ui
+                    .checkbox(&mut anneal, "Change exploration during each search")
+                    .on_hover_text("Typically a higher exploration early in a search and a lower one later, so the AI first looks at many moves and then focuses on the best ones.")
+                    .changed();
+                config.late_exploration_parameter = match config.late_exploration_parameter {
+                    Some(late) if anneal => Some(late),
+                    None if anneal => Some(config.exploration_parameter * 0.5),
+                    _ => None,
+                };
+                if let Some(late) = &mut config.late_exploration_parameter {
+                    changed |= ui
+                        .add(Slider::new(late, 0.0..=2.0).text("late exploration"))
+                        .on_hover_text("The exploration at the end of each search. In between, it moves gradually from the one above to this.")
+                        .changed();
+                }
+                if changed {
+                    for controller in &mut self.controllers {
+                        if let Controller::Ai(ai) = controller {
+                            ai.max_nodes = config.max_nodes;
+                            ai.exploration_parameter = config.exploration_parameter;
+                            ai.late_exploration_parameter = config.late_exploration_parameter;
+                        }
+                    }
+                }
+
+                egui::ComboBox::from_label("Coordinates")
+                    .selected_text(self.coordinate_system.name())
+                    .show_ui(ui, |ui| {
+                        for system in [CoordinateSystem::Letters, CoordinateSystem::Axial] {
+                            ui.selectable_value(&mut self.coordinate_system, system, system.name());
+                        }
+                    });
+                ui.checkbox(&mut self.show_coordinates, "Label coordinates on the board");
+
+                egui::ComboBox::from_label("Theme")
+                    .selected_text(self.theme.name())
+                    .show_ui(ui, |ui| {
+                        for theme in [
+                            ThemePreference::System,
+                            ThemePreference::Dark,
+                            ThemePreference::Light,
+                        ] {
+                            if ui
+                                .selectable_value(&mut self.theme, theme, theme.name())
+                                .changed()
+                            {
+                                ui.ctx().set_visuals(theme.visuals());
+                            }
+                        }
+                    });
+
+                ui.checkbox(&mut self.high_contrast, "High contrast board");
+
+                let previous_limit = self.undo_limit;
+                egui::ComboBox::from_label("Undo limit")
+                    .selected_text(self.undo_limit.name())
+                    .show_ui(ui, |ui| {
+                        for limit in [
+                            UndoLimit::Automatic,
+                            UndoLimit::Unlimited,
+                            UndoLimit::Limited(DEFAULT_UNDO_STEPS),
+                        ] {
+                            let selected = std::mem::discriminant(&self.undo_limit)
+                                == std::mem::discriminant(&limit);
+                            if ui.selectable_label(selected, limit.name()).clicked() && !selected {
+                                self.undo_limit = limit;
+                            }
+                        }
+                    });
+                if let UndoLimit::Limited(moves) = &mut self.undo_limit {
+                    ui.add(Slider::new(moves, 1..=500).text("moves"));
+                }
+                if self.undo_limit != previous_limit {
+                    let size = self.game.game.board.size();
+                    self.history.set_max_moves(self.undo_limit.max_moves(size));
+                }
+            });
+
+            ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
+                ui.horizontal(|ui| {
+                    ui.spacing_mut().item_spacing.x = 0.0;
+                    ui.label("powered by ");
+                    ui.hyperlink_to("egui", "https://github.com/emilk/egui");
+                    ui.label(" and ");
+                    ui.hyperlink_to("eframe", "https://github.com/emilk/egui/tree/master/eframe");
+                });
+            });
+        });
+        if self.controllers != controllers {
+            self.cancel_ai_move();
+        }
+
+        let size_overlay = (self.show_size_overlay
+            && self.size_slider_active
+            && self.configured_size != self.game.game.board.size())
+        .then(|| self.configured_size);
+        egui::CentralPanel::default().show(ctx, |ui| {
+            if !self.show_side_panel
+                && ui
+                    .small_button("⏵")
+                    .on_hover_text(TOGGLE_SIDE_PANEL_HINT)
+                    .clicked()
+            {
+                self.show_side_panel = true;
+            }
+
+            if let Status::Finished(winner) = self.game.game.status {
+                self.draw_victory_banner(ui, winner);
+            }
+            if self.ai_thinking() {
+                ui.label(format!(
+                    "{} is thinking\u{2026}",
+                    self.player_name(self.game.game.current_player)
+                ));
+            }
+
+            let comparison = self.comparison.take();
+            let sizing = self.board_sizing();
+            let theme = self.board_theme();
+            let rewound = self.rewind.map(|len| self.history.position_after(len));
+            match (&rewound, &comparison) {
+                (Some(rewound), _) => {
+                    egui::ScrollArea::both().show(ui, |ui| {
+                        ui.add(BoardView {
+                            board: &rewound.game.board,
+                            sizing,
+                            theme,
+                        })
+                    });
+                }
+                (None, Some(comparison)) => {
+                    ui.columns(2, |columns| {
+                        let viewport = columns[0].available_size();
+                        let widget = self.board_widget(size_overlay, viewport);
+                        egui::ScrollArea::both().show(&mut columns[0], |ui| ui.add(widget));
+                        columns[1].add(BoardView {
+                            board: &comparison.game.board,
+                            sizing,
+                            theme,
+                        });
+                    });
+                }
+                (None, None) => {
+                    let viewport = ui.available_size();
+                    let widget = self.board_widget(size_overlay, viewport);
+                    egui::ScrollArea::both().show(ui, |ui| ui.add(widget));
+                }
+            }
+            self.comparison = comparison;
+        });
+
+        if self.apply_move_events() {
+            ctx.request_repaint();
+        }
+    }
+}
+
+/// Where a board is drawn on screen, and how large.
+#[derive(Clone, Copy)]
+struct BoardLayout {
+    /// Screen position of the center of cell `(0, 0)`.
+    origin: Pos2,
+    /// Drawn size relative to [`geometry`], i.e. the cell size divided by `HEX_SIZE`.
+    scale: f32,
+    /// Distance from a hex's center to its corners at a scale of one.
+    hex_radius: f32,
+}
+
+impl BoardLayout {
+    /// Screen position of an offset like [`cell_offset`], which assumes a scale of one.
+    fn at(self, offset: Vec2) -> Pos2 {
+        self.origin + offset * self.scale
+    }
+
+    /// Screen position of the center of cell `(x, y)`.
+    fn cell(self, x: u8, y: u8) -> Pos2 {
+        self.at(cell_offset(x, y))
+    }
+
+    /// The corners of a hex centered on `center`.
+    fn hex(self, center: Pos2) -> Vec<Pos2> {
+        HEX_CORNERS
+            .iter()
+            .map(|&(x, y)| center + Vec2::new(x, y) * (self.hex_radius * self.scale))
+            .collect()
+    }
+
+    /// Whether `point` lies inside the hex centered on `center`, i.e. outside of the gap around it.
+    fn hex_contains(self, center: Pos2, point: Pos2) -> bool {
+        let offset = point - center;
+        geometry::hex_contains(offset.x, offset.y, self.hex_radius * self.scale)
+    }
+}
+
+/// How much room a board takes up, see [`allocate_board`].
+#[derive(Clone, Copy)]
+struct BoardSizing {
+    margin: f32,
+    cell_gap: f32,
+    min_cell_size: f32,
+    max_cell_size: f32,
+}
+
+/// Offset of the center of cell `(x, y)` from the center of cell `(0, 0)`.
+fn cell_offset(x: u8, y: u8) -> Vec2 {
+    board_point(x as f32, y as f32)
+}
+
+/// Like [`cell_offset`], but for fractional cell coordinates.
+fn board_point(x: f32, y: f32) -> Vec2 {
+    let (x, y) = geometry::board_point(x, y);
+    Vec2::new(x, y)
+}
+
+/// Draws each player's two goal edges in their color, with a name label `label_distance` points
+/// outside each edge.
+///
+/// The edges meet in the board's corners, so the corner cells touch the edges of both players. This
+/// matches the win condition, where a corner cell counts towards both connections.
+fn draw_edges(
+    painter: &Painter,
+    layout: BoardLayout,
+    size: u8,
+    theme: &Theme,
+    label_distance: f32,
+) {
+    let [top_left, top_right, bottom_left, bottom_right] =
+        geometry::edge_corners(size).map(|(x, y)| layout.at(Vec2::new(x, y)));
+    let center = top_left + (bottom_right - top_left) * 0.5;
+
+    for color in [Color::Black, Color::White] {
+        let stroke = Stroke::new(theme.edge_width, theme.player(color));
+        let edges = match board::goal_axis(color) {
+            Axis::X => [(top_left, bottom_left), (top_right, bottom_right)],
+            Axis::Y => [(top_left, top_right), (bottom_left, bottom_right)],
+        };
+        for (from, to) in edges {
+            painter.line_segment([from, to], stroke);
+            let middle = from + (to - from) * 0.5;
+            painter.text(
+                middle + (middle - center).normalized() * label_distance,
+                Align2::CENTER_CENTER,
+                board::color_name(color),
+                TextStyle::Small,
+                stroke.color,
+            );
+        }
+    }
+}
+
+/// Allocates the space for a board with `size` cells per side, scaled to fit into `viewport` within
+/// the limits of `sizing`.
+///
+/// Takes up at least the whole viewport, more if the board does not fit at the smallest cell size.
+/// Returns the allocated response, a painter clipped to it and a layout that centers the board.
+fn allocate_board(
+    ui: &mut Ui,
+    size: u8,
+    sizing: BoardSizing,
+    viewport: Vec2,
+    sense: Sense,
+) -> (Response, Painter, BoardLayout) {
+    let (space, layout) = fit_board(size, sizing, viewport);
+    let response = ui.allocate_response(space, sense);
+    let painter = ui.painter_at(response.rect);
+    let layout = BoardLayout {
+        origin: layout.origin + response.rect.center().to_vec2(),
+        ..layout
+    };
+    (response, painter, layout)
+}
+
+/// The space [`allocate_board`] takes up, and the layout of the board centered on the origin.
+fn fit_board(size: u8, sizing: BoardSizing, viewport: Vec2) -> (Vec2, BoardLayout) {
+    let margin = Vec2::splat(2.0 * sizing.margin);
+    let hex_radius = geometry::hex_radius(sizing.cell_gap);
+    let extent = board_extent(size, hex_radius);
+    let fit = ((viewport.x - margin.x) / extent.x).min((viewport.y - margin.y) / extent.y);
+    let scale = fit
+        .min(sizing.max_cell_size / HEX_SIZE)
+        .max(sizing.min_cell_size / HEX_SIZE);
+
+    let last = size.saturating_sub(1);
+    let layout = BoardLayout {
+        origin: Pos2::ZERO - cell_offset(last, last) * (scale * 0.5),
+        scale,
+        hex_radius,
+    };
+    (viewport.max(extent * scale + margin), layout)
+}
+
+/// Draws the goal edges and every cell of `board`.
+fn draw_board(painter: &Painter, layout: BoardLayout, board: &Board, theme: &Theme) {
+    draw_edges(painter, layout, board.size(), theme, EDGE_LABEL_DISTANCE);
+    draw_cells(painter, layout, board, theme);
+}
+
+/// Labels each column just outside the top edge and each row just outside the left edge, following
+/// the skew of the board.
+fn draw_coordinates(
+    painter: &Painter,
+    layout: BoardLayout,
+    size: u8,
+    system: CoordinateSystem,
+    theme: &Theme,
+) {
+    let [top_left, top_right, bottom_left, _] =
+        geometry::edge_corners(size).map(|(x, y)| layout.at(Vec2::new(x, y)));
+    // Perpendicular to each edge, pointing away from the board.
+    let up = (top_right - top_left).normalized().rot90();
+    let left = -(bottom_left - top_left).normalized().rot90();
+    let edge = -geometry::EDGE_DISTANCE;
+    let label = |offset: Vec2, outwards: Vec2, text: String| {
+        painter.text(
+            layout.at(offset) + outwards * COORDINATE_LABEL_DISTANCE,
+            Align2::CENTER_CENTER,
+            text,
+            TextStyle::Small,
+            theme.coordinate_text,
+        );
+    };
+    for i in 0..size {
+        label(
+            board_point(i as f32, edge),
+            up,
+            notation::column_label(i, system),
+        );
+        label(
+            board_point(edge, i as f32),
+            left,
+            notation::row_label(i, system),
+        );
+    }
+}
+
+fn draw_cells(painter: &Painter, layout: BoardLayout, board: &Board, theme: &Theme) {
+    let size = board.size();
+    for x in 0..size {
+        for y in 0..size {
+            let color = match board.get_color(Coords::new(x, y)) {
+                Some(color) => theme.player(color),
+                None => theme.empty_cell,
+            };
+
+            let hex_shape = layout.hex(layout.cell(x, y));
+            let line = Shape::convex_polygon(hex_shape, color, theme.grid);
+            painter.add(line);
+        }
+    }
+}
+
+/// A board that is only looked at, e.g. the position next to the game when comparing positions.
+struct BoardView<'a> {
+    board: &'a Board,
+    sizing: BoardSizing,
+    theme: Theme,
+}
+
+impl<'a> Widget for BoardView<'a> {
+    fn ui(self, ui: &mut Ui) -> Response {
+        let viewport = ui.available_size();
+        let (response, painter, layout) =
+            allocate_board(ui, self.board.size(), self.sizing, viewport, Sense::hover());
+        draw_board(&painter, layout, self.board, &self.theme);
+        response
+    }
+}
+
+/// Side length of the square a [`Thumbnail`] takes up.
+const THUMBNAIL_SIZE: f32 = 64.0;
+
+/// A tiny drawing of a board without its goal edges, e.g. the final position of a recent game.
+struct Thumbnail<'a> {
+    board: &'a Board,
+    theme: Theme,
+}
+
+impl<'a> Widget for Thumbnail<'a> {
+    fn ui(self, ui: &mut Ui) -> Response {
+        let sizing = BoardSizing {
+            margin: 2.0,
+            cell_gap: 0.0,
+            min_cell_size: 1.0,
+            max_cell_size: HEX_SIZE,
+        };
+        let (response, painter, layout) = allocate_board(
+            ui,
+            self.board.size(),
+            sizing,
+            Vec2::splat(THUMBNAIL_SIZE),
+            Sense::hover(),
+        );
+        let theme = Theme {
+            grid: Stroke::none(),
+            ..self.theme
+        };
+        draw_cells(&painter, layout, self.board, &theme);
+        response
+    }
+}
+
+/// Size of the smallest rectangle that contains every hex of a board with `size` cells per side.
+fn board_extent(size: u8, hex_radius: f32) -> Vec2 {
+    let (width, height) = geometry::board_extent(size, hex_radius);
+    Vec2::new(width, height)
+}
+
+/// How many finished games [`HexGameUi::recent_games`] keeps.
+const MAX_RECENT_GAMES: usize = 10;
+
+/// A finished game in the list of recent games.
+#[cfg_attr(feature = "persistence", derive(serde::Deserialize, serde::Serialize))]
+struct RecentGame {
+    /// The moves in the notation of [`notation::format_game`].
+    game: String,
+    summary: String,
+}
+
+/// Which game actions are available, see [`HexGameUi::button_states`].
+struct ButtonStates {
+    reset: bool,
+    undo: bool,
+    redo: bool,
+    resign: bool,
+    hint: bool,
+    swap: bool,
+    force_ai_move: bool,
+}
+
+/// A change to the game, queued while drawing and applied by [`HexGameUi::apply_move_events`] at
+/// the end of the frame.
+///
+/// Routing every placement through this queue keeps the board widget free of game logic.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum MoveEvent {
+    /// The human clicked `coords` at input time `time`.
+    Place { coords: Coords, time: f64 },
+    /// The human asked to remove the stone on the given cell. Only honored in analysis mode.
+    Remove(Coords),
+    /// The human clicked on the board, but not on a cell. See [`OffBoardClick`].
+    ClickedOffBoard,
+    /// The AI's reply is due. It chooses its move at input time `time`.
+    ///
+    /// Like the other AI events, it is dropped unless `generation` is still the app's
+    /// `ai_generation`.
+    AiReply { time: f64, generation: u64 },
+    /// The AI's move, chosen early, is due at input time `time`.
+    AiChosen {
+        coords: Coords,
+        time: f64,
+        generation: u64,
+    },
+    /// The AI's previewed move is due.
+    AiMove { coords: Coords, generation: u64 },
+}
+
+struct HexWidget<'a> {
+    game: &'a MctsHexGame,
+    sizing: BoardSizing,
+    theme: Theme,
+    /// The space the board is scaled to fit into.
+    viewport: Vec2,
+    last_search: &'a Option<Suggestion>,
+    show_candidates: bool,
+    show_bridges: bool,
+    show_coordinates: bool,
+    coordinate_system: CoordinateSystem,
+    /// Size of a board to outline on top of the current one.
+    size_overlay: Option<u8>,
+    animations: bool,
+    /// See [`HexGameUi::victory_flow_speed`].
+    victory_flow_speed: f32,
+    /// Offset of the hover highlight like [`cell_offset`].
+    hover_highlight: &'a mut Option<Vec2>,
+    /// The cell the AI is about to play.
+    ai_preview: Option<Coords>,
+    /// The cell of the last rejected click, with the input time of the click.
+    rejected_click: Option<(Coords, f64)>,
+    debug_overlay: bool,
+    /// The cell suggested by "Hint" for the current position.
+    hint: Option<Coords>,
+    /// See [`HexGameUi::last_human_move`].
+    last_human_move: Option<Coords>,
+    last_ai_move: Option<Coords>,
+    /// The last move of a drill, if it matched the opening.
+    drill_correct: Option<Coords>,
+    /// Whether each empty cell wins with perfect play, see [`oracle::solve`].
+    oracle: &'a [(Coords, bool)],
+    /// Also place stones where a drag on the board is released.
+    drag_to_place: bool,
+    /// Receives the moves requested by clicks on the board.
+    move_events: &'a mut Vec<MoveEvent>,
+}
+
+impl<'a> Widget for HexWidget<'a> {
+    fn ui(self, ui: &mut Ui) -> Response {
+        self.draw_game(ui)
+    }
+}
+
+impl<'a> HexWidget<'a> {
+    fn draw_game(mut self, ui: &mut Ui) -> Response {
+        let game = self.game;
+        let board = &game.game.board;
+        let size = board.size();
+        // A finished game stays on screen, but no longer reacts to the pointer.
+        let ongoing = matches!(game.game.status, Status::Ongoing);
+
+        let (response, painter, layout) = allocate_board(
+            ui,
+            size,
+            self.sizing,
+            self.viewport,
+            Sense::click_and_drag(),
+        );
+
+        let mut closest_coord = None;
+        let mut closest_distance = f32::MAX;
+
+        let pointer = &ui.input().pointer;
+
+        let pos = |x, y| layout.cell(x, y);
+
+        for x in 0..size {
+            for y in 0..size {
+                if let Some(cursor_pos) = pointer.hover_pos().filter(|_| ongoing) {
+                    // Only the drawn hex reacts to the pointer, not the gap around it.
+                    let distance_sq = cursor_pos.distance_sq(pos(x, y));
+                    let is_within_hex = layout.hex_contains(pos(x, y), cursor_pos);
+                    let is_closest = distance_sq < closest_distance;
+                    if is_within_hex && is_closest {
+                        closest_distance = distance_sq;
+                        closest_coord = Some((x, y))
+                    }
+                }
+            }
+        }
+
+        let theme = self.theme;
+        if self.show_coordinates {
+            let label_distance = EDGE_LABEL_DISTANCE + COORDINATE_LABEL_SPACE;
+            draw_edges(&painter, layout, size, &theme, label_distance);
+            draw_cells(&painter, layout, board, &theme);
+            draw_coordinates(&painter, layout, size, self.coordinate_system, &theme);
+        } else {
+            draw_board(&painter, layout, board, &theme);
+        }
+
+        if let Some(overlay_size) = self.size_overlay {
+            let overlay_last = overlay_size - 1;
+            let overlay = BoardLayout {
+                origin: response.rect.center()
+                    - cell_offset(overlay_last, overlay_last) * (layout.scale * 0.5),
+                ..layout
+            };
+            for x in 0..overlay_size {
+                for y in 0..overlay_size {
+                    let hex_shape = overlay.hex(overlay.cell(x, y));
+                    painter.add(Shape::closed_line(hex_shape, theme.size_overlay));
+                }
+            }
+        }
+
+        if self.show_bridges {
+            let player = game.game.current_player;
+            let stroke = Stroke::new(
+                theme.bridge_width,
+                theme.player(player).linear_multiply(0.5),
+            );
+            for (from, to) in board::find_bridges(board, player) {
+                let (from_x, from_y) = board::to_xy(from);
+                let (to_x, to_y) = board::to_xy(to);
+                painter.line_segment([pos(from_x, from_y), pos(to_x, to_y)], stroke);
+            }
+        }
+
+        if self.show_candidates {
+            if let Some(search) = self.last_search {
+                for (rank, candidate) in search.candidates.iter().enumerate() {
+                    if board.get_color(candidate.action).is_none() {
+                        let (x, y) = board::to_xy(candidate.action);
+                        painter.text(
+                            pos(x, y),
+                            Align2::CENTER_CENTER,
+                            rank + 1,
+                            TextStyle::Body,
+                            theme.candidate_text,
+                        );
+                    }
+                }
+            }
+        }
+
+        for &(coords, wins) in self.oracle {
+            let (x, y) = board::to_xy(coords);
+            let color = if wins {
+                theme.oracle_win
+            } else {
+                theme.oracle_loss
+            };
+            painter.circle_filled(pos(x, y), 6.0 * layout.scale, color);
+        }
+
+        if let Status::Finished(winner) = game.game.status {
+            if let Some(path) = board::winning_path(board, winner) {
+                self.draw_winning_path(ui, &painter, layout, &path);
+            }
+        }
+
+        if let Some(coords) = self.last_human_move {
+            let (x, y) = board::to_xy(coords);
+            let radius = theme.last_move_size * layout.hex_radius * layout.scale;
+            painter.circle_filled(pos(x, y), radius, theme.last_move);
+        }
+        if let Some(coords) = self.last_ai_move {
+            let (x, y) = board::to_xy(coords);
+            let radius = theme.last_move_size * layout.hex_radius * layout.scale;
+            painter.circle_stroke(
+                pos(x, y),
+                radius,
+                Stroke::new(theme.last_move_width, theme.last_move),
+            );
+        }
+
+        let hover_target = closest_coord
+            .filter(|&(x, y)| board.get_color(Coords::new(x, y)).is_none())
+            .map(|(x, y)| cell_offset(x, y));
+        self.draw_hover_highlight(ui, &painter, &response, layout, hover_target);
+
+        if let Some((coords, time)) = self.rejected_click {
+            let age = ui.input().time - time;
+            if age < REJECTED_CLICK_FLASH {
+                let (x, y) = board::to_xy(coords);
+                let opacity = 1.0 - (age / REJECTED_CLICK_FLASH) as f32;
+                let hex_shape = layout.hex(pos(x, y));
+                painter.add(Shape::convex_polygon(
+                    hex_shape,
+                    theme.rejected_fill.linear_multiply(opacity),
+                    Stroke::new(
+                        theme.rejected_outline.width,
+                        theme.rejected_outline.color.linear_multiply(opacity),
+                    ),
+                ));
+                ui.ctx().request_repaint();
+            }
+        }
+
+        if self.debug_overlay {
+            painter.text(
+                response.rect.left_top() + Vec2::splat(4.0),
+                Align2::LEFT_TOP,
+                format!("position hash: {:016x}", board::position_hash(board)),
+                TextStyle::Monospace,
+                theme.debug_text,
+            );
+        }
+
+        if let Some(coords) = self.hint {
+            let (x, y) = board::to_xy(coords);
+            painter.add(Shape::closed_line(layout.hex(pos(x, y)), theme.hint));
+        }
+
+        if let Some(coords) = self.drill_correct {
+            let (x, y) = board::to_xy(coords);
+            painter.add(Shape::closed_line(
+                layout.hex(pos(x, y)),
+                theme.drill_correct,
+            ));
+        }
+
+        if let Some(coords) = self.ai_preview {
+            let (x, y) = board::to_xy(coords);
+            let flash = (ui.input().time * 10.0).sin() as f32 * 0.5 + 0.5;
+            let color = theme
+                .player(game.game.current_player)
+                .linear_multiply(0.4 + 0.6 * flash);
+            painter.add(Shape::closed_line(
+                layout.hex(pos(x, y)),
+                Stroke::new(theme.preview_width, color),
+            ));
+        }
+
+        if self.drag_to_place && response.dragged() {
+            if let Some((x, y)) = closest_coord {
+                if board.get_color(Coords::new(x, y)).is_none() {
+                    let color = theme.player(game.game.current_player).linear_multiply(0.5);
+                    painter.add(Shape::convex_polygon(
+                        layout.hex(pos(x, y)),
+                        color,
+                        Stroke::none(),
+                    ));
+                }
+            }
+        }
+
+        // `clicked` is only set if the pointer stayed put, so drags never count as clicks. A drag
+        // released next to the cells is ignored.
+        match closest_coord {
+            Some((x, y)) => {
+                let coords = Coords::new(x, y);
+                let released = self.drag_to_place && response.drag_released();
+                if response.clicked() || released {
+                    self.move_events.push(MoveEvent::Place {
+                        coords,
+                        time: ui.input().time,
+                    });
+                }
+                if response.secondary_clicked() {
+                    self.move_events.push(MoveEvent::Remove(coords));
+                }
+            }
+            None if ongoing && response.clicked() => {
+                self.move_events.push(MoveEvent::ClickedOffBoard);
+            }
+            None => {}
+        }
+
+        match closest_coord {
+            Some((x, y)) => response.on_hover_text(notation::format_coord(
+                Coords::new(x, y),
+                self.coordinate_system,
+            )),
+            None => response,
+        }
+    }
+
+    /// Outlines the cells of the winning `path` and traces it with a line. With animations enabled, a
+    /// bright pulse flows along it from one edge to the other.
+    fn draw_winning_path(&self, ui: &Ui, painter: &Painter, layout: BoardLayout, path: &[Coords]) {
+        let points: Vec<_> = path
+            .iter()
+            .map(|&coords| {
+                let (x, y) = board::to_xy(coords);
+                layout.cell(x, y)
+            })
+            .collect();
+        for &point in &points {
+            painter.add(Shape::closed_line(
+                layout.hex(point),
+                self.theme.winning_cells,
+            ));
+        }
+        let width = 6.0 * layout.scale;
+        if !self.animations {
+            painter.add(Shape::line(
+                points,
+                Stroke::new(width, self.theme.winning_path),
+            ));
+            return;
+        }
+
+        let cycle = path.len() as f64 + VICTORY_PULSE_LENGTH as f64;
+        let head = ((ui.input().time * self.victory_flow_speed as f64) % cycle) as f32;
+        for (index, segment) in points.windows(2).enumerate() {
+            // How far the head of the pulse has moved past the start of this segment.
+            let behind = head - index as f32;
+            let glow = if (0.0..VICTORY_PULSE_LENGTH).contains(&behind) {
+                1.0 - behind / VICTORY_PULSE_LENGTH
+            } else {
+                0.0
+            };
+            let color = self.theme.winning_path.linear_multiply(0.4 + 0.6 * glow);
+            painter.line_segment([segment[0], segment[1]], Stroke::new(width, color));
+        }
+        ui.ctx().request_repaint();
+    }
+
+    /// Outlines the hovered empty cell in the color of the side to move. With animations enabled,
+    /// the outline glides from cell to cell and fades in and out.
+    ///
+    /// `target` is the hovered empty cell's offset like [`cell_offset`].
+    fn draw_hover_highlight(
+        &mut self,
+        ui: &Ui,
+        painter: &Painter,
+        response: &Response,
+        layout: BoardLayout,
+        target: Option<Vec2>,
+    ) {
+        let (current, opacity) = if self.animations {
+            let current = match (target, *self.hover_highlight) {
+                (Some(target), Some(current)) => {
+                    let step = (ui.input().unstable_dt * HOVER_ANIMATION_SPEED).min(1.0);
+                    let current = current + (target - current) * step;
+                    if (target - current).length() > 0.5 {
+                        ui.ctx().request_repaint();
+                    }
+                    Some(current)
+                }
+                (Some(target), None) => Some(target),
+                // Keep the last position while fading out.
+                (None, current) => current,
+            };
+            let opacity = ui
+                .ctx()
+                .animate_bool(response.id.with("hover_highlight"), target.is_some());
+            (current, opacity)
+        } else {
+            (target, 1.0)
+        };
+
+        *self.hover_highlight = current.filter(|_| opacity > 0.0);
+        if let Some(current) = *self.hover_highlight {
+            let color = self
+                .theme
+                .player(self.game.game.current_player)
+                .linear_multiply(opacity);
+            let hex_shape = layout.hex(layout.at(current));
+            painter.add(Shape::closed_line(
+                hex_shape,
+                Stroke::new(self.theme.hover_width, color),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{distributions::Standard, Rng};
+
+    /// Waits for the search of [`HexGameUi::start_search`] and uses its result.
+    fn finish_search_job(app: &mut HexGameUi) {
+        while app.search_job.is_some() {
+            std::thread::yield_now();
+            app.poll_search_job();
+        }
+    }
+
+    #[test]
+    fn console_searches_report_when_they_finish() {
+        let mut app = HexGameUi::default();
+        app.run_console_command("eval");
+        app.run_console_command("go 50");
+        assert_eq!(
+            app.console_output.last().map(String::as_str),
+            Some("busy, wait for the running search")
+        );
+        finish_search_job(&mut app);
+        assert!(app
+            .console_output
+            .last()
+            .unwrap()
+            .starts_with("Red to move: "));
+
+        app.run_console_command("go 50");
+        assert!(app.play(Coords::new(2, 2)));
+        finish_search_job(&mut app);
+        assert_eq!(
+            app.console_output.last().map(String::as_str),
+            Some("the position changed during the search")
+        );
+    }
+
+    #[test]
+    fn hints_do_not_change_the_ai_rng() {
+        let mut app = HexGameUi {
+            deterministic: true,
+            seed: 7,
+            ..HexGameUi::default()
+        };
+        app.reset_rng();
+        let mut untouched = app.rng.clone();
+        app.show_hint();
+        finish_search_job(&mut app);
+        assert!(app.hint.is_some());
+        assert_eq!(app.rng.gen::<u64>(), untouched.gen::<u64>());
+    }
+
+    #[test]
+    fn ai_rng_advances_between_searches() {
+        let mut app = HexGameUi {
+            deterministic: true,
+            seed: 7,
+            ..HexGameUi::default()
+        };
+        let stream = |rng: SmallRng| -> Vec<u64> { rng.sample_iter(Standard).take(4).collect() };
+        app.reset_rng();
+        let first = stream(app.ai_search_rng());
+        let second = stream(app.ai_search_rng());
+        assert_ne!(first, second);
+
+        // The streams are still reproducible from the seed.
+        app.reset_rng();
+        assert_eq!(stream(app.ai_search_rng()), first);
+    }
+
+    #[test]
+    fn clicks_within_debounce_window_play_one_move() {
+        let mut app = HexGameUi {
+            analysis_mode: true,
+            ..HexGameUi::default()
+        };
+        let place = |x, y, time| MoveEvent::Place {
+            coords: Coords::new(x, y),
+            time,
+        };
+        app.move_events = vec![
+            place(0, 0, 1.0),
+            place(1, 1, 1.0 + CLICK_DEBOUNCE / 2.0),
+            place(2, 2, 1.0 + CLICK_DEBOUNCE * 2.0),
+        ];
+        assert!(app.apply_move_events());
+
+        let played: Vec<_> = app.history.all_moves().map(|m| m.coords).collect();
+        assert_eq!(played, [Coords::new(0, 0), Coords::new(2, 2)]);
+    }
+
+    #[test]
+    fn off_board_click_only_clears_hint_when_configured() {
+        let mut app = HexGameUi::default();
+        let position = board::position_hash(&app.game.game.board);
+        app.hint = Some((Coords::new(1, 1), position));
+
+        app.move_events = vec![MoveEvent::ClickedOffBoard];
+        assert!(!app.apply_move_events());
+        assert!(app.hint.is_some());
+
+        app.off_board_click = OffBoardClick::ClearHint;
+        app.move_events = vec![MoveEvent::ClickedOffBoard];
+        assert!(app.apply_move_events());
+        assert!(app.hint.is_none());
+        assert!(app.history.is_empty());
+    }
+
+    #[test]
+    fn hint_clears_when_the_human_moves_or_resets() {
+        let mut app = HexGameUi::default();
+        app.show_hint();
+        finish_search_job(&mut app);
+        let (hinted, _) = app.hint.expect("no hint on an empty board");
+        assert_eq!(board::count_stones(&app.game.game.board), 0);
+
+        assert!(app.place_human_move(hinted, 0.0));
+        assert!(app.hint.is_none());
+        app.undo();
+        assert!(app.hint.is_none());
+
+        app.show_hint();
+        finish_search_job(&mut app);
+        app.reset_game();
+        assert!(app.hint.is_none());
+    }
+
+    #[test]
+    fn board_fits_the_viewport_and_cells_match_the_pointer() {
+        let sizing = HexGameUi::default().board_sizing();
+        let viewport = Vec2::new(800.0, 600.0);
+        for size in [3, 17] {
+            let (space, layout) = fit_board(size, sizing, viewport);
+            assert_eq!(space, viewport, "size {} does not fit", size);
+            let last = size - 1;
+            for (x, y) in [
+                (0, 0),
+                (last, 0),
+                (0, last),
+                (last, last),
+                (size / 2, size / 2),
+            ] {
+                let center = layout.cell(x, y);
+                assert!(space.x * 0.5 > center.x.abs() && space.y * 0.5 > center.y.abs());
+                let next = layout.cell(x.saturating_sub(1), y);
+                let near_edge = center + (next - center) * 0.45;
+                assert!(layout.hex_contains(center, near_edge) || next == center);
+                assert!(!layout.hex_contains(next, near_edge) || next == center);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "persistence")]
+    fn game_in_progress_survives_a_restart() {
+        let mut app = HexGameUi::default();
+        assert!(app.play(Coords::new(2, 2)));
+        assert!(app.play(Coords::new(1, 3)));
+        app.save_game_in_progress();
+
+        let mut restored = HexGameUi {
+            saved_game: app.saved_game.clone(),
+            ..HexGameUi::default()
+        };
+        restored.restore_saved_game();
+        assert_eq!(
+            board::position_hash(&restored.game.game.board),
+            board::position_hash(&app.game.game.board)
+        );
+        assert_eq!(restored.history.all_moves().count(), 2);
+
+        let mut broken = HexGameUi {
+            saved_game: Some("5 zz9".to_owned()),
+            ..HexGameUi::default()
+        };
+        broken.restore_saved_game();
+        assert!(broken.history.is_empty());
+        assert_eq!(broken.game.game.board.size(), broken.configured_size);
+    }
+
+    #[test]
+    fn ai_moving_first_opens_without_human_input() {
+        let ai = Controller::Ai(AiConfig {
+            iterations: 10,
+            ..AiConfig::default()
+        });
+        let mut app = HexGameUi {
+            controllers: [ai, Controller::Human],
+            ..HexGameUi::default()
+        };
+        app.reset_game();
+        // Frames far enough apart for every delay to pass, until the background search is done.
+        for frame in 0..1000 {
+            app.advance_ai(frame as f64 * 10.0);
+            app.apply_move_events();
+            if !app.history.is_empty() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+
+        let moves: Vec<_> = app.history.all_moves().collect();
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].color, Color::Black);
+        assert!(app.is_human_turn());
+    }
+
+    #[test]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn ai_search_results_only_apply_to_their_position() {
+        let mut app = HexGameUi::default();
+        assert!(app.play(Coords::new(2, 2)));
+        let searched = board::position_key(&app.game);
+        let (sender, receiver) = mpsc::channel();
+        sender
+            .send(Some(AiReply::Move(Suggestion {
+                action: Coords::new(0, 0),
+                win_probability: 0.5,
+                candidates: Vec::new(),
+                visit_share: 1.0,
+            })))
+            .unwrap();
+        // The side to move changed while the AI searched.
+        app.game.game.current_player = Color::Black;
+        app.ai_search = Some((searched, receiver));
+        app.poll_ai_search(0.0);
+        assert!(app.ai_search.is_none() && app.held_ai_move.is_none());
+        assert_eq!(app.history.all_moves().count(), 1);
+
+        // A search without a move does not pass the turn.
+        app.game.game.current_player = Color::White;
+        let (sender, receiver) = mpsc::channel();
+        sender.send(None).unwrap();
+        app.ai_search = Some((searched, receiver));
+        app.poll_ai_search(0.0);
+        assert!(app.ai_gave_up());
+        assert_eq!(app.game.game.current_player, Color::White);
+
+        app.ai_failed = None;
+        let (sender, receiver) = mpsc::channel::<Option<AiReply>>();
+        drop(sender);
+        app.ai_search = Some((searched, receiver));
+        app.advance_ai(0.0);
+        assert!(app.ai_gave_up());
+        app.advance_ai(1.0);
+        assert!(!app.ai_thinking() && app.ai_move_due.is_none());
+    }
+
+    #[test]
+    fn undo_and_redo_move_by_human_turns() {
+        let mut app = HexGameUi::default();
+        // The human's move and the AI's reply.
+        assert!(app.play(Coords::new(2, 2)));
+        assert!(app.play(Coords::new(1, 3)));
+        assert!(app.button_states().undo);
+        assert!(!app.button_states().redo);
+
+        app.undo();
+        assert!(app.history.is_empty());
+        assert_eq!(board::count_stones(&app.game.game.board), 0);
+        assert!(!app.button_states().undo);
+        assert!(app.button_states().redo);
+
+        app.redo();
+        assert_eq!(app.history.all_moves().count(), 2);
+        assert_eq!(board::count_stones(&app.game.game.board), 2);
+        assert!(!app.button_states().redo);
+
+        app.undo();
+        app.reset_game();
+        assert!(!app.button_states().redo);
+    }
+
+    #[test]
+    fn last_moves_are_marked_until_taken_back() {
+        let mut app = HexGameUi::default();
+        assert!(app.place_human_move(Coords::new(2, 2), 0.0));
+        app.play_ai_move(Coords::new(1, 3));
+        assert_eq!(app.last_human_move, Some(Coords::new(2, 2)));
+        assert_eq!(app.last_ai_move, Some(Coords::new(1, 3)));
+
+        app.undo();
+        assert_eq!((app.last_human_move, app.last_ai_move), (None, None));
+        assert!(app.place_human_move(Coords::new(0, 0), 1.0));
+        app.new_game(5);
+        assert_eq!(app.last_human_move, None);
+    }
+
+    #[test]
+    fn swap_takes_over_the_first_stone_on_move_two_only() {
+        let mut app = HexGameUi {
+            controllers: [Controller::Human, Controller::Human],
+            swap_rule: true,
+            ..HexGameUi::default()
+        };
+        assert!(!app.can_swap());
+        assert!(app.play(Coords::new(0, 1)));
+        assert!(app.can_swap());
+
+        app.swap();
+        let board = &app.game.game.board;
+        assert_eq!(board::count_stones(board), 1);
+        assert_eq!(board.get_color(Coords::new(1, 0)), Some(Color::White));
+        assert_eq!(app.game.game.current_player, Color::Black);
+        assert!(!app.can_swap());
+
+        app.undo();
+        assert_eq!(
+            app.game.game.board.get_color(Coords::new(0, 1)),
+            Some(Color::Black)
+        );
+        app.redo();
+        assert_eq!(
+            app.game.game.board.get_color(Coords::new(1, 0)),
+            Some(Color::White)
+        );
+
+        assert!(app.play(Coords::new(2, 2)));
+        assert!(!app.can_swap());
+    }
+
+    #[test]
+    fn reset_drops_queued_ai_move() {
+        let mut app = HexGameUi::default();
+        app.move_events.push(MoveEvent::AiMove {
+            coords: Coords::new(1, 1),
+            generation: app.ai_generation,
+        });
+        // The reset button is handled after the AI's move was queued, but before it is applied.
+        app.reset_game();
+        app.apply_move_events();
+
+        assert!(app.history.is_empty());
+        assert_eq!(board::count_stones(&app.game.game.board), 0);
+    }
+
+    #[test]
+    fn drill_only_accepts_the_openings_moves() {
+        let mut app = HexGameUi::default();
+        let (size, name, moves) = app
+            .openings
+            .iter()
+            .flat_map(|(&size, openings)| {
+                openings
+                    .iter()
+                    .map(move |(name, moves)| (size, name.clone(), moves.clone()))
+            })
+            .find(|(_, _, moves)| !moves.is_empty())
+            .expect("an opening is shipped");
+        app.start_drill(size, name);
+        let wrong = (0..size)
+            .map(|x| Coords::new(x, size - 1))
+            .find(|&coords| coords != moves[0])
+            .unwrap();
+
+        app.move_events = vec![MoveEvent::Place {
+            coords: wrong,
+            time: 1.0,
+        }];
+        app.apply_move_events();
+        assert!(app.history.is_empty());
+        assert_eq!(app.drill.as_ref().unwrap().mistakes, 1);
+
+        app.move_events = vec![MoveEvent::Place {
+            coords: moves[0],
+            time: 2.0,
+        }];
+        app.apply_move_events();
+        assert_eq!(app.history.all_moves().count(), 1);
+        assert_eq!(
+            app.drill.as_ref().unwrap().feedback,
+            Some(Feedback::Correct(moves[0]))
+        );
+    }
+
+    #[test]
+    fn win_probability_follows_the_latest_rated_move() {
+        let mut app = HexGameUi::default();
+        assert_eq!(app.evaluation_perspective(), Color::Black);
+        assert!(app.place_human_move(Coords::new(2, 2), 0.0));
+        assert_eq!(app.win_probability(Color::Black), None);
+
+        app.play_ai_move(Coords::new(1, 3));
+        app.history.set_last_eval(0.75);
+        assert_eq!(app.win_probability(Color::White), Some(0.75));
+        assert_eq!(app.win_probability(Color::Black), Some(0.25));
+        // Still the AI's rating after the human's next move.
+        assert!(app.place_human_move(Coords::new(0, 0), 1.0));
+        assert_eq!(app.win_probability(Color::Black), Some(0.25));
+    }
+
+    #[test]
+    fn watching_the_ai_returns_control_when_stopped() {
+        let mut app = HexGameUi::default();
+        let controllers = app.controllers;
+        app.set_watching(true);
+        assert!(app
+            .controllers
+            .iter()
+            .all(|&controller| controller != Controller::Human));
+
+        assert!(app.play(Coords::new(2, 2)));
+        app.advance_ai(0.0);
+        app.set_watching(false);
+        assert_eq!(app.controllers, controllers);
+        assert!(!app.ai_thinking() && app.ai_move_due.is_none());
+    }
+
+    #[test]
+    fn watching_the_ai_keeps_changes_to_the_ai() {
+        let mut app = HexGameUi {
+            controllers: [Controller::Human, Controller::Ai(AiConfig::default())],
+            ..HexGameUi::default()
+        };
+        app.set_watching(true);
+        let stronger = AiConfig {
+            iterations: 20_000,
+            ..AiConfig::default()
+        };
+        app.controllers[1] = Controller::Ai(stronger);
+        app.set_watching(false);
+        assert_eq!(
+            app.controllers,
+            [Controller::Human, Controller::Ai(stronger)]
+        );
+    }
+
+    #[test]
+    fn status_line_follows_the_game() {
+        let mut app = HexGameUi::default();
+        assert_eq!(app.status_line(), (Color::Black, "Red to move".to_owned()));
+        assert!(app.play(Coords::new(2, 2)));
+        assert_eq!(app.status_line(), (Color::White, "Blue to move".to_owned()));
+
+        app.resign();
+        assert_eq!(
+            app.status_line(),
+            (Color::Black, "Red won, Blue resigned".to_owned())
+        );
+        app.undo();
+        assert_eq!(app.status_line(), (Color::Black, "Red to move".to_owned()));
+    }
+
+    #[test]
+    fn game_text_round_trips_and_rejects_bad_input() {
+        let mut app = HexGameUi::default();
+        assert!(app.play(Coords::new(2, 2)));
+        assert!(app.play(Coords::new(3, 1)));
+        let text = app.game_text();
+        assert_eq!(text, "5 c3 d2");
+
+        let mut loaded = HexGameUi::default();
+        loaded.load_game_text(&text).unwrap();
+        assert_eq!(loaded.game_text(), text);
+        assert_eq!(loaded.last_ai_move, Some(Coords::new(3, 1)));
+        assert_eq!(loaded.last_human_move, None);
+
+        for bad in [
+            "5 c3 ??",
+            "5 c3 c3",
+            "5 z9",
+            "banana",
+            "5 swap",
+            "5 c3 d2 swap",
+        ] {
+            assert!(loaded.load_game_text(bad).is_err(), "{} was accepted", bad);
+            assert_eq!(loaded.game_text(), text);
+        }
+    }
+
+    #[test]
+    fn blunder_checks_rate_the_move_they_checked() {
+        let mut app = HexGameUi::default();
+        assert!(app.play(Coords::new(2, 2)));
+        assert!(app.play(Coords::new(1, 1)));
+        for (number, coords) in [(1, Coords::new(2, 2)), (2, Coords::new(3, 3))] {
+            let (sender, receiver) = mpsc::channel();
+            sender
+                .send(Some(BlunderCheck {
+                    before: 0.9,
+                    after: 0.5,
+                }))
+                .unwrap();
+            app.blunder_checks.push((number, coords, receiver));
+        }
+        app.poll_blunder_checks();
+
+        assert!(app.blunder_checks.is_empty());
+        let moves: Vec<_> = app.history.all_moves().collect();
+        assert_eq!(moves[0].eval, Some(0.5));
+        assert!(moves[0].blunder.is_some());
+        // The second check was for a move that is not on the board.
+        assert_eq!(moves[1].eval, None);
+    }
+
+    #[test]
+    fn adaptive_difficulty_changes_a_time_budget() {
+        let config = AiConfig {
+            budget: SearchBudget::Time(1.0),
+            ..AiConfig::default()
+        };
+        let mut app = HexGameUi {
+            controllers: [Controller::Human, Controller::Ai(config)],
+            adaptive_difficulty: true,
+            ..HexGameUi::default()
+        };
+        app.load_game_text("3 a2 a1 b2 a3").unwrap();
+        assert!(app.play(Coords::new(2, 1)));
+        app.adjust_difficulty();
+
+        let expected = AiConfig {
+            budget: SearchBudget::Time(1.0 + DIFFICULTY_TIME_STEP),
+            ..config
+        };
+        assert_eq!(app.controllers[1], Controller::Ai(expected));
+    }
+
+    #[test]
+    fn loaded_finished_games_do_not_count_again() {
+        let mut app = HexGameUi::default();
+        app.load_game_text("3 a2 a1 b2 a3 c2").unwrap();
+        assert!(matches!(
+            app.game.game.status,
+            Status::Finished(Color::Black)
+        ));
+        assert!(app.difficulty_adjusted);
+        assert!(app.recent_game_recorded);
+        #[cfg(not(target_arch = "wasm32"))]
+        assert!(app.game_logged);
+
+        app.load_game_text("3 a2 a1").unwrap();
+        assert!(!app.difficulty_adjusted);
+        assert!(!app.recent_game_recorded);
+    }
+
+    #[test]
+    fn edited_positions_are_not_exact_game_texts() {
+        let mut app = HexGameUi::default();
+        assert!(app.play(Coords::new(2, 2)));
+        assert!(app.game_text_is_exact());
+
+        app.game.game.current_player = Color::Black;
+        assert!(!app.game_text_is_exact());
+    }
+
+    #[test]
+    fn game_text_keeps_swaps() {
+        let mut app = HexGameUi {
+            swap_rule: true,
+            ..HexGameUi::default()
+        };
+        assert!(app.play(Coords::new(1, 3)));
+        app.swap();
+        assert!(app.play(Coords::new(2, 2)));
+        let text = app.game_text();
+        assert_eq!(text, "5 b4 swap c3");
+
+        let mut loaded = HexGameUi::default();
+        loaded.load_game_text(&text).unwrap();
+        assert_eq!(loaded.game_text(), text);
+        assert_eq!(
+            board::position_hash(&loaded.game.game.board),
+            board::position_hash(&app.game.game.board)
+        );
+        assert_eq!(
+            loaded.game.game.current_player,
+            app.game.game.current_player
+        );
+    }
+
+    #[test]
+    fn every_offered_size_can_be_played() {
+        let mut app = HexGameUi::default();
+        for size in MIN_BOARD_SIZE..=MAX_BOARD_SIZE {
+            app.new_game(size);
+            assert_eq!(app.game.game.board.size(), size);
+            let last = size - 1;
+            assert!(app.play(Coords::new(last, last)), "size {}", size);
+        }
+        app.new_game(0);
+        assert_eq!(app.game.game.board.size(), MIN_BOARD_SIZE);
+    }
+
+    #[test]
+    #[cfg(feature = "persistence")]
+    fn restoring_size_zero_yields_playable_state() {
+        let mut app: HexGameUi = ron::from_str("(configured_size: 0)").unwrap();
+        app.sanitize_restored_state();
+
+        assert_eq!(app.configured_size, MIN_BOARD_SIZE);
+        assert!(app.game.game.board.size() >= MIN_BOARD_SIZE);
     }
 }
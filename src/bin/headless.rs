@@ -0,0 +1,160 @@
+#![forbid(unsafe_code)]
+#![cfg_attr(not(debug_assertions), deny(warnings))] // Forbid warnings in release builds
+#![warn(clippy::all, rust_2018_idioms)]
+
+//! Plays a single AI-vs-AI game without opening a window and prints the move list and result.
+//!
+//! With `--sizes`, plays `--games` games on each of the listed board sizes in turn instead and
+//! prints the results per size.
+//!
+//! Usage: `cargo run --bin headless -- [--size N] [--sizes N,N,...] [--games N] [--iterations N] [--exploration X] [--late-exploration X] [--seed N] [--resign X]`
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {
+    use hex_ui::{
+        ai::AiConfig,
+        board::color_name,
+        headless,
+        notation::{format_coord, CoordinateSystem},
+    };
+    use rand::{prelude::SmallRng, SeedableRng};
+
+    let mut size = 5;
+    let mut sizes: Option<Vec<u8>> = None;
+    let mut games = 10;
+    let mut config = AiConfig::default();
+    let mut seed = None;
+    let mut resign_threshold = 0.0;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .unwrap_or_else(|| usage(&format!("missing value for {}", arg)));
+        match arg.as_str() {
+            "--size" => size = parse_size(&arg, &value),
+            "--sizes" => {
+                sizes = Some(
+                    value
+                        .split(',')
+                        .map(|size| parse_size(&arg, size.trim()))
+                        .collect(),
+                )
+            }
+            "--games" => games = parse_count(&arg, &value),
+            "--iterations" => config.iterations = parse_count(&arg, &value),
+            "--exploration" => config.exploration_parameter = parse(&arg, &value),
+            "--late-exploration" => config.late_exploration_parameter = Some(parse(&arg, &value)),
+            "--seed" => seed = Some(parse(&arg, &value)),
+            "--resign" => resign_threshold = parse(&arg, &value),
+            _ => usage(&format!("unknown argument {}", arg)),
+        }
+    }
+
+    let mut rng = match seed {
+        Some(seed) => SmallRng::seed_from_u64(seed),
+        None => SmallRng::from_entropy(),
+    };
+
+    if let Some(sizes) = sizes {
+        println!(
+            "{} games per size, {} iterations, exploration {}{}",
+            games,
+            config.iterations,
+            config.exploration_parameter,
+            late_exploration(&config)
+        );
+        println!("size  games  first player wins  abandoned  average moves");
+        for size in sizes {
+            let result = headless::play_size(size, &config, games, resign_threshold, &mut rng);
+            println!(
+                "{:>4}  {:>5}  {:>17}  {:>9}  {:>13.1}",
+                result.size,
+                result.games(),
+                result
+                    .first_player_win_rate()
+                    .map_or_else(|| "-".to_owned(), |rate| format!("{:.0}%", rate * 100.0)),
+                result.abandoned,
+                result.average_moves()
+            );
+        }
+        return;
+    }
+
+    println!(
+        "Size {}, {} iterations, exploration {}{}",
+        size,
+        config.iterations,
+        config.exploration_parameter,
+        late_exploration(&config)
+    );
+    let game = headless::play_ai_vs_ai(size, &config, resign_threshold, &mut rng);
+    for (index, &coords) in game.moves.iter().enumerate() {
+        println!(
+            "{}. {}",
+            index + 1,
+            format_coord(coords, CoordinateSystem::Letters)
+        );
+    }
+    match game.winner {
+        Some(winner) => println!(
+            "Result: {} wins after {} moves{}",
+            color_name(winner),
+            game.moves.len(),
+            if game.resigned { " by resignation" } else { "" }
+        ),
+        None => println!(
+            "Result: abandoned after {} moves, the AI found no move",
+            game.moves.len()
+        ),
+    }
+}
+
+/// Describes the late exploration parameter for the summary line, if one is set.
+#[cfg(not(target_arch = "wasm32"))]
+fn late_exploration(config: &hex_ui::ai::AiConfig) -> String {
+    config
+        .late_exploration_parameter
+        .map_or_else(String::new, |late| format!(" to {}", late))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn parse<T: std::str::FromStr>(arg: &str, value: &str) -> T {
+    value
+        .parse()
+        .unwrap_or_else(|_| usage(&format!("invalid value for {}: {}", arg, value)))
+}
+
+/// Parses a board size. `board::empty_game` would silently clamp an unsupported size.
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_size(arg: &str, value: &str) -> u8 {
+    use hex_ui::board::{MAX_BOARD_SIZE, MIN_BOARD_SIZE};
+
+    let size = parse(arg, value);
+    if !(MIN_BOARD_SIZE..=MAX_BOARD_SIZE).contains(&size) {
+        usage(&format!(
+            "{} must be between {} and {}: {}",
+            arg, MIN_BOARD_SIZE, MAX_BOARD_SIZE, value
+        ));
+    }
+    size
+}
+
+/// Parses a number of games or iterations, of which there has to be at least one.
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_count(arg: &str, value: &str) -> u32 {
+    let count = parse(arg, value);
+    if count == 0 {
+        usage(&format!("{} must be at least 1", arg));
+    }
+    count
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn usage(error: &str) -> ! {
+    eprintln!("error: {}", error);
+    eprintln!(
+        "usage: headless [--size N] [--sizes N,N,...] [--games N] [--iterations N] [--exploration X] [--late-exploration X] [--seed N] [--resign X]"
+    );
+    std::process::exit(2)
+}
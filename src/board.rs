@@ -0,0 +1,462 @@
+use std::collections::VecDeque;
+
+use hexgame::{Board, Color, Coords, Status};
+use hexgame_ai::MctsHexGame;
+
+/// Smallest board size that still allows a meaningful game.
+pub const MIN_BOARD_SIZE: u8 = 2;
+pub const MAX_BOARD_SIZE: u8 = 17;
+
+/// An empty board of the given size, with Black to move. Sizes outside of
+/// [`MIN_BOARD_SIZE`]`..=`[`MAX_BOARD_SIZE`] are clamped, as a board without cells cannot be played.
+pub fn empty_game(size: u8) -> MctsHexGame {
+    MctsHexGame::new(size.clamp(MIN_BOARD_SIZE, MAX_BOARD_SIZE), 0, 1)
+}
+
+/// Identifies a pair of opposite board edges by the coordinate that is constant along each of them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+}
+
+/// The pair of edges `color` has to connect.
+///
+/// `hexgame` lets Black connect its first and last row, which is the `x` coordinate of [`to_xy`].
+pub fn goal_axis(color: Color) -> Axis {
+    match color {
+        Color::Black => Axis::X,
+        Color::White => Axis::Y,
+    }
+}
+
+/// Splits `coords` into the `(x, y)` pair used by the renderer, i.e. the arguments of `Coords::new`.
+pub fn to_xy(coords: Coords) -> (u8, u8) {
+    (coords.row, coords.column)
+}
+
+/// The name shown to the user for `color`. Black stones are drawn red, white stones blue.
+pub fn color_name(color: Color) -> &'static str {
+    match color {
+        Color::Black => "Red",
+        Color::White => "Blue",
+    }
+}
+
+/// Number of cells of `board` that hold a stone of either color.
+pub fn count_stones(board: &Board) -> usize {
+    let size = board.size();
+    (0..size)
+        .flat_map(|x| (0..size).map(move |y| Coords::new(x, y)))
+        .filter(|&coords| board.get_color(coords).is_some())
+        .count()
+}
+
+/// Offsets `(dx, dy)` from a cell to its neighbors.
+const NEIGHBOR_OFFSETS: [(i8, i8); 6] = [(1, 0), (-1, 0), (0, 1), (0, -1), (1, -1), (-1, 1)];
+
+/// The cells next to `coords` on a board with `size` cells per side.
+pub fn neighbors(coords: Coords, size: u8) -> impl Iterator<Item = Coords> {
+    let (x, y) = to_xy(coords);
+    NEIGHBOR_OFFSETS.iter().filter_map(move |&(dx, dy)| {
+        let (x, y) = (x as i8 + dx, y as i8 + dy);
+        if (0..size as i8).contains(&x) && (0..size as i8).contains(&y) {
+            Some(Coords::new(x as u8, y as u8))
+        } else {
+            None
+        }
+    })
+}
+
+/// Distance of `coords` from the center of a board with `size` cells per side, in steps between
+/// neighboring cells. Can be fractional on boards with an even size.
+pub fn distance_from_center(coords: Coords, size: u8) -> f32 {
+    let (x, y) = to_xy(coords);
+    let center = (size as f32 - 1.0) * 0.5;
+    let (dx, dy) = (x as f32 - center, y as f32 - center);
+    (dx.abs() + dy.abs() + (dx + dy).abs()) * 0.5
+}
+
+/// The fewest stones `player` still needs to connect its edges, ignoring the opponent's replies.
+///
+/// `0` once the edges are connected, `None` if the opponent has cut them apart.
+pub fn remaining_distance(board: &Board, player: Color) -> Option<u8> {
+    let size = board.size();
+    let index = |coords: Coords| {
+        let (x, y) = to_xy(coords);
+        x as usize * size as usize + y as usize
+    };
+    // The coordinate along `player`'s goal axis, from one of its edges to the other.
+    let progress = |coords: Coords| {
+        let (x, y) = to_xy(coords);
+        match goal_axis(player) {
+            Axis::X => x,
+            Axis::Y => y,
+        }
+    };
+    let cost = |coords: Coords| match board.get_color(coords) {
+        Some(color) if color == player => Some(0),
+        Some(_) => None,
+        None => Some(1),
+    };
+
+    // A breadth-first search where own stones cost nothing and empty cells one stone.
+    let mut distances = vec![u8::MAX; size as usize * size as usize];
+    let mut queue = VecDeque::new();
+    for x in 0..size {
+        for y in 0..size {
+            let coords = Coords::new(x, y);
+            if progress(coords) == 0 {
+                if let Some(cost) = cost(coords) {
+                    distances[index(coords)] = cost;
+                    queue.push_back(coords);
+                }
+            }
+        }
+    }
+    while let Some(coords) = queue.pop_front() {
+        let distance = distances[index(coords)];
+        for neighbor in neighbors(coords, size) {
+            if let Some(cost) = cost(neighbor) {
+                if distance + cost < distances[index(neighbor)] {
+                    distances[index(neighbor)] = distance + cost;
+                    if cost == 0 {
+                        queue.push_front(neighbor);
+                    } else {
+                        queue.push_back(neighbor);
+                    }
+                }
+            }
+        }
+    }
+
+    (0..size)
+        .flat_map(|x| (0..size).map(move |y| Coords::new(x, y)))
+        .filter(|&coords| progress(coords) == size - 1)
+        .map(|coords| distances[index(coords)])
+        .min()
+        .filter(|&distance| distance != u8::MAX)
+}
+
+/// The shortest chain of `player`'s stones that connects its edges, ordered from the edge at
+/// coordinate `0` to the opposite one. `None` if the edges are not connected.
+pub fn winning_path(board: &Board, player: Color) -> Option<Vec<Coords>> {
+    let size = board.size();
+    let progress = |coords: Coords| {
+        let (x, y) = to_xy(coords);
+        match goal_axis(player) {
+            Axis::X => x,
+            Axis::Y => y,
+        }
+    };
+    let owned = |coords: Coords| board.get_color(coords) == Some(player);
+    let index = |coords: Coords| {
+        let (x, y) = to_xy(coords);
+        x as usize * size as usize + y as usize
+    };
+
+    // A breadth-first search over the stones of `player`. `reached_from` holds the stone each
+    // stone was reached from, `Some(None)` for the stones on the first edge.
+    let mut reached_from: Vec<Option<Option<Coords>>> = vec![None; size as usize * size as usize];
+    let mut queue: VecDeque<_> = (0..size)
+        .flat_map(|x| (0..size).map(move |y| Coords::new(x, y)))
+        .filter(|&coords| progress(coords) == 0 && owned(coords))
+        .collect();
+    for &start in &queue {
+        reached_from[index(start)] = Some(None);
+    }
+    while let Some(coords) = queue.pop_front() {
+        if progress(coords) == size - 1 {
+            let mut path = vec![coords];
+            while let Some(Some(before)) =
+                reached_from[index(*path.last().expect("path is not empty"))]
+            {
+                path.push(before);
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for neighbor in neighbors(coords, size) {
+            if owned(neighbor) && reached_from[index(neighbor)].is_none() {
+                reached_from[index(neighbor)] = Some(Some(coords));
+                queue.push_back(neighbor);
+            }
+        }
+    }
+    None
+}
+
+/// Offsets `(dx, dy)` to the far end of a bridge, each with the two cells that carry it.
+///
+/// Only one direction of every pair is listed, so each bridge is found once.
+const BRIDGE_PATTERNS: [((i8, i8), [(i8, i8); 2]); 3] = [
+    ((1, 1), [(1, 0), (0, 1)]),
+    ((2, -1), [(1, -1), (1, 0)]),
+    ((1, -2), [(0, -1), (1, -1)]),
+];
+
+/// Finds the bridges of `player`: pairs of its stones that share two empty neighboring cells.
+///
+/// A bridge cannot be cut, since the player can answer an intrusion into one carrier cell by taking
+/// the other one.
+pub fn find_bridges(board: &Board, player: Color) -> Vec<(Coords, Coords)> {
+    let size = board.size() as i8;
+    let cell = |x: i8, y: i8| {
+        if (0..size).contains(&x) && (0..size).contains(&y) {
+            Some(Coords::new(x as u8, y as u8))
+        } else {
+            None
+        }
+    };
+
+    let mut bridges = Vec::new();
+    for x in 0..size {
+        for y in 0..size {
+            let from = Coords::new(x as u8, y as u8);
+            if board.get_color(from) != Some(player) {
+                continue;
+            }
+            for &((dx, dy), carriers) in &BRIDGE_PATTERNS {
+                let to = match cell(x + dx, y + dy) {
+                    Some(to) if board.get_color(to) == Some(player) => to,
+                    _ => continue,
+                };
+                let carriers_empty = carriers.iter().all(|&(cx, cy)| {
+                    cell(x + cx, y + cy).map_or(false, |carrier| board.get_color(carrier).is_none())
+                });
+                if carriers_empty {
+                    bridges.push((from, to));
+                }
+            }
+        }
+    }
+    bridges
+}
+
+/// A fixed pseudo random number for `index`, from the SplitMix64 generator.
+///
+/// The Zobrist keys of [`position_hash`] are computed with this instead of being drawn from `rand`,
+/// so hashes stay the same across versions and platforms.
+pub(crate) fn splitmix64(index: u64) -> u64 {
+    let mut z = index.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// A Zobrist hash of the stones on `board` and its size.
+///
+/// The hash only depends on the position, not on the order of the moves that led to it, and is
+/// stable across runs, so it can be quoted in bug reports and test fixtures.
+pub fn position_hash(board: &Board) -> u64 {
+    let size = board.size();
+    let cell_count = u64::from(MAX_BOARD_SIZE) * u64::from(MAX_BOARD_SIZE);
+    let mut hash = splitmix64(2 * cell_count + u64::from(size));
+    for x in 0..size {
+        for y in 0..size {
+            let color_index = match board.get_color(Coords::new(x, y)) {
+                Some(Color::Black) => 0,
+                Some(Color::White) => 1,
+                None => continue,
+            };
+            let cell = u64::from(x) * u64::from(MAX_BOARD_SIZE) + u64::from(y);
+            hash ^= splitmix64(color_index * cell_count + cell);
+        }
+    }
+    hash
+}
+
+/// Index of `color` in arrays that hold something per player: `0` for Black, `1` for White.
+pub fn color_index(color: Color) -> usize {
+    match color {
+        Color::Black => 0,
+        Color::White => 1,
+    }
+}
+
+/// The other player.
+pub fn opponent(color: Color) -> Color {
+    match color {
+        Color::Black => Color::White,
+        Color::White => Color::Black,
+    }
+}
+
+/// Identifies a position including the side to move, e.g. to tell whether a search result is still
+/// for the current position.
+pub fn position_key(game: &MctsHexGame) -> u64 {
+    let side_to_move = match game.game.current_player {
+        Color::Black => 0,
+        Color::White => 0x9e37_79b9_7f4a_7c15,
+    };
+    position_hash(&game.game.board) ^ side_to_move
+}
+
+/// Places a stone of `color` on `coords`, regardless of whose turn it is.
+///
+/// Afterwards it is the opponent of `color` to move. Returns `false` if the move was rejected.
+pub fn place_stone(game: &mut MctsHexGame, coords: Coords, color: Color) -> bool {
+    game.game.current_player = color;
+    game.play(coords).is_ok()
+}
+
+/// The position after the side to move swaps sides under the swap rule: every stone is mirrored
+/// across the long diagonal and changes its color, and the other side is to move.
+///
+/// Mirroring keeps every stone at the same place relative to its new owner's edges, so the swapping
+/// player takes over the opponent's position exactly.
+pub fn swap_sides(game: &MctsHexGame) -> MctsHexGame {
+    let board = &game.game.board;
+    let size = board.size();
+    let mut swapped = empty_game(size);
+    for x in 0..size {
+        for y in 0..size {
+            if let Some(color) = board.get_color(Coords::new(x, y)) {
+                place_stone(&mut swapped, Coords::new(y, x), opponent(color));
+            }
+        }
+    }
+    swapped.game.current_player = opponent(game.game.current_player);
+    swapped
+}
+
+/// A cell on which a stone of `player` wins the game right away, if there is one.
+///
+/// Tries every empty cell, which is far cheaper than a search.
+pub fn immediate_winning_move(game: &MctsHexGame, player: Color) -> Option<Coords> {
+    let board = &game.game.board;
+    let size = board.size();
+    (0..size)
+        .flat_map(|x| (0..size).map(move |y| Coords::new(x, y)))
+        .filter(|&coords| board.get_color(coords).is_none())
+        .find(|&coords| {
+            let mut after = game.clone();
+            place_stone(&mut after, coords, player)
+                && matches!(after.game.status, Status::Finished(winner) if winner == player)
+        })
+}
+
+/// Returns a copy of `game` with the stone on `coords` removed. The side to move is kept.
+///
+/// `hexgame` cannot take stones back, so the position is rebuilt from the remaining stones.
+pub fn remove_stone(game: &MctsHexGame, coords: Coords) -> MctsHexGame {
+    let board = &game.game.board;
+    let size = board.size();
+    let mut edited = empty_game(size);
+    for x in 0..size {
+        for y in 0..size {
+            let cell = Coords::new(x, y);
+            if cell == coords {
+                continue;
+            }
+            if let Some(color) = board.get_color(cell) {
+                place_stone(&mut edited, cell, color);
+            }
+        }
+    }
+    edited.game.current_player = game.game.current_player;
+    edited
+}
+
+/// A move as written down for a whole game, see [`replay`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameMove {
+    Stone(Coords),
+    /// Swapping sides under the swap rule, see [`swap_sides`]. Only the second move can be a swap.
+    Swap,
+}
+
+/// Plays `moves` in order on an empty board of the given size.
+///
+/// Returns a description of the first problem if the size is unsupported or a move cannot be played.
+pub fn replay(size: u8, moves: &[GameMove]) -> Result<MctsHexGame, String> {
+    if !(MIN_BOARD_SIZE..=MAX_BOARD_SIZE).contains(&size) {
+        return Err(format!(
+            "Board size {} is not between {} and {}",
+            size, MIN_BOARD_SIZE, MAX_BOARD_SIZE
+        ));
+    }
+
+    let mut game = empty_game(size);
+    for (index, &game_move) in moves.iter().enumerate() {
+        let coords = match game_move {
+            GameMove::Stone(coords) => coords,
+            GameMove::Swap if index == 1 && matches!(game.game.status, Status::Ongoing) => {
+                game = swap_sides(&game);
+                continue;
+            }
+            GameMove::Swap => return Err(format!("Move {} cannot be a swap", index + 1)),
+        };
+        let (x, y) = to_xy(coords);
+        if x >= size || y >= size {
+            return Err(format!("Move {} is outside the board", index + 1));
+        }
+        if game.play(coords).is_err() {
+            return Err(format!("Move {} cannot be played", index + 1));
+        }
+    }
+    Ok(game)
+}
+
+/// The game after playing the stones `moves`, given as `(x, y)`, on an empty board of the given
+/// size.
+#[cfg(test)]
+pub(crate) fn test_game(size: u8, moves: &[(u8, u8)]) -> MctsHexGame {
+    let moves: Vec<_> = moves
+        .iter()
+        .map(|&(x, y)| GameMove::Stone(Coords::new(x, y)))
+        .collect();
+    replay(size, &moves).expect("scripted moves are legal")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn position_hash_ignores_move_order() {
+        let hash = |moves: &[(u8, u8)]| position_hash(&test_game(3, moves).game.board);
+
+        let expected = hash(&[(1, 1), (0, 0), (2, 0), (0, 2)]);
+        assert_eq!(expected, hash(&[(2, 0), (0, 2), (1, 1), (0, 0)]));
+        assert_ne!(expected, hash(&[(0, 0), (1, 1), (0, 2), (2, 0)]));
+        assert_ne!(expected, hash(&[]));
+    }
+
+    #[test]
+    fn finds_immediate_winning_move() {
+        // Red has a row from the left edge to the second to last column, so one more stone connects
+        // it.
+        let red = [(0, 2), (1, 2), (2, 2), (3, 2)];
+        let blue = [(0, 0), (1, 0), (2, 0), (0, 4)];
+        let moves: Vec<_> = red.iter().zip(&blue).flat_map(|(&r, &b)| [r, b]).collect();
+        let game = test_game(5, &moves);
+
+        let winning = immediate_winning_move(&game, Color::Black).expect("red can win");
+        assert!(matches!(to_xy(winning), (4, 1) | (4, 2)));
+        assert_eq!(immediate_winning_move(&game, Color::White), None);
+    }
+
+    #[test]
+    fn winning_path_connects_the_winners_edges() {
+        let game = test_game(3, &[(0, 1), (0, 0), (1, 1), (0, 2), (2, 1)]);
+
+        let path = winning_path(&game.game.board, Color::Black).expect("red has won");
+        let path: Vec<_> = path.into_iter().map(to_xy).collect();
+        assert_eq!(path, [(0, 1), (1, 1), (2, 1)]);
+        assert_eq!(winning_path(&game.game.board, Color::White), None);
+    }
+
+    #[test]
+    fn remaining_distance_counts_missing_stones() {
+        let empty = test_game(5, &[]);
+        assert_eq!(remaining_distance(&empty.game.board, Color::Black), Some(5));
+
+        // Red's two stones lead diagonally to its stone on the far edge, a gap on each side. Blue's
+        // column along the near edge misses two cells.
+        let game = test_game(5, &[(1, 2), (0, 0), (2, 2), (0, 1), (4, 0), (0, 3)]);
+        let board = &game.game.board;
+        assert_eq!(remaining_distance(board, Color::Black), Some(2));
+        assert_eq!(remaining_distance(board, Color::White), Some(2));
+    }
+}
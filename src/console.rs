@@ -0,0 +1,48 @@
+//! Commands of the engine console, a text interface to the AI for advanced users.
+
+/// A parsed console command.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    /// Search the current position, with the configured iteration count unless one is given.
+    Go(Option<u32>),
+    /// Print the estimated win probability of the side to move.
+    Eval,
+    /// Replace the game with a position in the notation of [`crate::notation::format_game`].
+    SetPosition(String),
+    /// Print the current position in the notation `SetPosition` accepts.
+    GetPosition,
+    Help,
+}
+
+/// One line per command, shown by `help`.
+pub const HELP: &str = "go [iterations]: search the current position\n\
+    eval: estimate the win probability of the side to move\n\
+    setpos <size> <moves>: set up a position, e.g. `setpos 5 c3 d5`\n\
+    getpos: print the current position\n\
+    help: show this list";
+
+/// Parses one line typed into the console.
+pub fn parse_command(line: &str) -> Result<Command, String> {
+    let line = line.trim();
+    let (name, arguments) = match line.split_once(char::is_whitespace) {
+        Some((name, arguments)) => (name, arguments.trim()),
+        None => (line, ""),
+    };
+
+    match name {
+        "go" if arguments.is_empty() => Ok(Command::Go(None)),
+        "go" => match arguments.parse() {
+            Ok(iterations) if iterations > 0 => Ok(Command::Go(Some(iterations))),
+            _ => Err(format!("Invalid iteration count: {}", arguments)),
+        },
+        "eval" => Ok(Command::Eval),
+        "setpos" if arguments.is_empty() => Err("setpos needs a position".to_owned()),
+        "setpos" => Ok(Command::SetPosition(arguments.to_owned())),
+        "getpos" => Ok(Command::GetPosition),
+        "help" => Ok(Command::Help),
+        _ => Err(format!(
+            "Unknown command: {}. Type `help` for a list.",
+            name
+        )),
+    }
+}
@@ -0,0 +1,74 @@
+//! Drills that ask the user to play a named opening from memory, move by move.
+
+use hexgame::Coords;
+
+/// Identifies the opening `name` on boards of the given size among the completed drills, which are
+/// kept across sessions.
+pub fn key(size: u8, name: &str) -> String {
+    format!("{}: {}", size, name)
+}
+
+/// How the last move tried in a drill compared to the opening.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Feedback {
+    Correct(Coords),
+    /// The move was rejected, the opening continues elsewhere.
+    Wrong(Coords),
+}
+
+/// An opening being practiced.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Drill {
+    pub size: u8,
+    pub name: String,
+    moves: Vec<Coords>,
+    /// Moves tried that did not match the opening.
+    pub mistakes: u32,
+    pub feedback: Option<Feedback>,
+}
+
+impl Drill {
+    pub fn new(size: u8, name: String, moves: Vec<Coords>) -> Self {
+        Self {
+            size,
+            name,
+            moves,
+            mistakes: 0,
+            feedback: None,
+        }
+    }
+
+    pub fn key(&self) -> String {
+        key(self.size, &self.name)
+    }
+
+    pub fn move_count(&self) -> usize {
+        self.moves.len()
+    }
+
+    /// The move the opening continues with after `played` moves, `None` once it is complete.
+    pub fn expected(&self, played: usize) -> Option<Coords> {
+        self.moves.get(played).copied()
+    }
+
+    pub fn is_complete(&self, played: usize) -> bool {
+        played >= self.moves.len()
+    }
+
+    /// Compares `coords`, tried after `played` moves, against the opening and records the result.
+    /// Returns whether the move may be played. Any move may be played once the opening is complete.
+    pub fn check(&mut self, coords: Coords, played: usize) -> bool {
+        match self.expected(played) {
+            Some(expected) if expected == coords => {
+                self.feedback = Some(Feedback::Correct(coords));
+                true
+            }
+            Some(_) => {
+                self.mistakes += 1;
+                self.feedback = Some(Feedback::Wrong(coords));
+                false
+            }
+            None => true,
+        }
+    }
+}
@@ -0,0 +1,18 @@
+use std::{
+    fs::OpenOptions,
+    io::{self, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Appends `entry` to the log file at `path`, creating the file if needed.
+///
+/// Every entry starts with a line holding the time it was written, in seconds since the Unix epoch.
+pub fn append_entry(path: &Path, entry: &str) -> io::Result<()> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "# {}", timestamp)?;
+    writeln!(file, "{}", entry)
+}
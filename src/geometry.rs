@@ -0,0 +1,80 @@
+//! Layout of the board, shared by the on-screen board and the exports. Distances are in points.
+
+/// Horizontal distance between the centers of neighboring cells.
+pub const HEX_SIZE: f32 = 40.0;
+/// The gap between neighboring hexes used by the exports, see [`hex_radius`].
+pub const DEFAULT_CELL_GAP: f32 = 0.05;
+/// Distance from a hex's center to its corners with the default gap.
+pub const HEX_RADIUS: f32 = HEX_SIZE * (1.0 - DEFAULT_CELL_GAP) * 0.5 / 0.86603;
+/// Distance of the drawn goal edges from the outermost cell centers, in cells.
+pub const EDGE_DISTANCE: f32 = 0.75;
+
+/// Corners of a hex with a radius of one, relative to its center.
+pub const HEX_CORNERS: [(f32, f32); 6] = [
+    (0.00000, 1.00000),
+    (0.86603, 0.50000),
+    (0.86603, -0.50000),
+    (0.00000, -1.00000),
+    (-0.86603, -0.50000),
+    (-0.86603, 0.50000),
+];
+
+/// Distance from a hex's center to its corners when neighboring hexes are `gap` apart, as a
+/// fraction of `HEX_SIZE`. A gap of zero tiles the plane without holes.
+pub fn hex_radius(gap: f32) -> f32 {
+    HEX_SIZE * (1.0 - gap) * 0.5 / 0.86603
+}
+
+/// Whether the offset `(x, y)` from a hex's center lies inside the hex with the given radius.
+pub fn hex_contains(x: f32, y: f32, radius: f32) -> bool {
+    let (x, y) = ((x / radius).abs(), (y / radius).abs());
+    x <= 0.86603 && y <= 1.0 - x * 0.57735
+}
+
+/// Offset of the point at fractional cell coordinates `(x, y)` from the center of cell `(0, 0)`.
+pub fn board_point(x: f32, y: f32) -> (f32, f32) {
+    (HEX_SIZE * x + y * HEX_SIZE * 0.5, HEX_SIZE * y * 0.87)
+}
+
+/// The corners of the goal edges of a board with `size` cells per side, as offsets like
+/// [`board_point`]: top left, top right, bottom left and bottom right.
+pub fn edge_corners(size: u8) -> [(f32, f32); 4] {
+    let near = -EDGE_DISTANCE;
+    let far = size as f32 - 1.0 + EDGE_DISTANCE;
+    [
+        board_point(near, near),
+        board_point(far, near),
+        board_point(near, far),
+        board_point(far, far),
+    ]
+}
+
+/// The smallest rectangle that contains every hex and goal edge of a board with `size` cells per
+/// side, as `(min_x, min_y, max_x, max_y)` in offsets like [`board_point`].
+pub fn drawing_bounds(size: u8) -> (f32, f32, f32, f32) {
+    let hex_points = (0..size)
+        .flat_map(|x| (0..size).map(move |y| (x, y)))
+        .flat_map(|(x, y)| {
+            let (center_x, center_y) = board_point(x as f32, y as f32);
+            HEX_CORNERS
+                .iter()
+                .map(move |&(dx, dy)| (center_x + dx * HEX_RADIUS, center_y + dy * HEX_RADIUS))
+        });
+    hex_points.chain(edge_corners(size)).fold(
+        (f32::MAX, f32::MAX, f32::MIN, f32::MIN),
+        |(min_x, min_y, max_x, max_y), (x, y)| {
+            (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y))
+        },
+    )
+}
+
+/// Size of the smallest rectangle that contains every hex of a board with `size` cells per side,
+/// for hexes with the given radius.
+pub fn board_extent(size: u8, hex_radius: f32) -> (f32, f32) {
+    let last = size.saturating_sub(1) as f32;
+    let (width, height) = board_point(last, last);
+    (
+        width + 2.0 * 0.86603 * hex_radius,
+        height + 2.0 * hex_radius,
+    )
+}
@@ -0,0 +1,139 @@
+use hexgame::{Color, Coords, Status};
+use rand::prelude::SmallRng;
+
+use crate::{
+    ai::{self, AiConfig},
+    board,
+};
+
+/// The outcome of a game played without a window.
+pub struct HeadlessGame {
+    pub size: u8,
+    pub moves: Vec<Coords>,
+    /// `None` if the game was abandoned because the AI did not come up with a move.
+    pub winner: Option<Color>,
+    /// Whether the loser resigned instead of the winner completing a connection.
+    pub resigned: bool,
+}
+
+/// The combined outcome of several AI-vs-AI games on one board size.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SizeResult {
+    pub size: u8,
+    /// Games won by the first player, Black.
+    pub first_player_wins: u32,
+    pub second_player_wins: u32,
+    /// Games in which the AI did not come up with a move.
+    pub abandoned: u32,
+    /// Moves played in all games together.
+    pub total_moves: usize,
+}
+
+impl SizeResult {
+    pub fn games(&self) -> u32 {
+        self.first_player_wins + self.second_player_wins + self.abandoned
+    }
+
+    /// How often the first player won the decided games. `None` if no game was decided.
+    pub fn first_player_win_rate(&self) -> Option<f32> {
+        let decided = self.first_player_wins + self.second_player_wins;
+        if decided == 0 {
+            None
+        } else {
+            Some(self.first_player_wins as f32 / decided as f32)
+        }
+    }
+
+    pub fn average_moves(&self) -> f32 {
+        if self.games() == 0 {
+            0.0
+        } else {
+            self.total_moves as f32 / self.games() as f32
+        }
+    }
+}
+
+/// Lets the AI play `games` games against itself on a board of the given size and sums up the
+/// results.
+pub fn play_size(
+    size: u8,
+    config: &AiConfig,
+    games: u32,
+    resign_threshold: f32,
+    rng: &mut SmallRng,
+) -> SizeResult {
+    let mut result = SizeResult {
+        size,
+        ..SizeResult::default()
+    };
+    for _ in 0..games {
+        let game = play_ai_vs_ai(size, config, resign_threshold, rng);
+        result.total_moves += game.moves.len();
+        match game.winner {
+            Some(Color::Black) => result.first_player_wins += 1,
+            Some(Color::White) => result.second_player_wins += 1,
+            None => result.abandoned += 1,
+        }
+    }
+    result
+}
+
+/// Estimates how often the first player wins on a board of the given size by letting the AI play
+/// `games` games against itself.
+///
+/// Abandoned games are not counted. Returns `None` if no game was decided.
+pub fn first_player_win_rate(
+    size: u8,
+    config: &AiConfig,
+    games: u32,
+    rng: &mut SmallRng,
+) -> Option<f32> {
+    play_size(size, config, games, 0.0, rng).first_player_win_rate()
+}
+
+/// Lets the AI play against itself on an empty board of the given size until one side wins.
+///
+/// A side resigns once its estimated win probability drops below `resign_threshold`.
+pub fn play_ai_vs_ai(
+    size: u8,
+    config: &AiConfig,
+    resign_threshold: f32,
+    rng: &mut SmallRng,
+) -> HeadlessGame {
+    let mut game = board::empty_game(size);
+    let mut moves = Vec::new();
+
+    loop {
+        if let Status::Finished(winner) = game.game.status {
+            return HeadlessGame {
+                size,
+                moves,
+                winner: Some(winner),
+                resigned: false,
+            };
+        }
+
+        let suggestion = match ai::suggest(&game, config, rng) {
+            Some(suggestion) => suggestion,
+            None => {
+                return HeadlessGame {
+                    size,
+                    moves,
+                    winner: None,
+                    resigned: false,
+                }
+            }
+        };
+        if ai::should_resign(&suggestion, resign_threshold) {
+            return HeadlessGame {
+                size,
+                moves,
+                winner: Some(board::opponent(game.game.current_player)),
+                resigned: true,
+            };
+        }
+        game.play(suggestion.action)
+            .expect("Failed to play AI move");
+        moves.push(suggestion.action);
+    }
+}
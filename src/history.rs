@@ -0,0 +1,233 @@
+use std::collections::VecDeque;
+
+use hexgame::{Color, Coords};
+use hexgame_ai::MctsHexGame;
+
+use crate::board;
+
+/// A stone placement as recorded in the history.
+///
+/// The color is stored explicitly because analysis mode may place stones out of turn.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Move {
+    pub coords: Coords,
+    pub color: Color,
+    /// How much the mover's estimated win probability dropped, if the move was flagged as a blunder.
+    pub blunder: Option<f32>,
+    /// The mover's estimated win probability after the move, if the AI rated it.
+    pub eval: Option<f32>,
+    /// Whether the move was a swap, see [`board::swap_sides`]. `coords` is then the mirrored stone.
+    pub swap: bool,
+}
+
+impl Move {
+    /// The move as written down for the whole game.
+    pub fn game_move(&self) -> board::GameMove {
+        if self.swap {
+            board::GameMove::Swap
+        } else {
+            board::GameMove::Stone(self.coords)
+        }
+    }
+
+    fn apply(&self, game: &mut MctsHexGame) {
+        if self.swap {
+            *game = board::swap_sides(game);
+        } else {
+            board::place_stone(game, self.coords, self.color);
+        }
+    }
+}
+
+/// How many moves the history retains for undoing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "persistence", derive(serde::Deserialize, serde::Serialize))]
+pub enum UndoLimit {
+    /// Unlimited on boards up to `AUTOMATIC_UNLIMITED_SIZE`, `AUTOMATIC_LIMIT` moves on larger ones.
+    Automatic,
+    Unlimited,
+    Limited(usize),
+}
+
+const AUTOMATIC_UNLIMITED_SIZE: u8 = 11;
+const AUTOMATIC_LIMIT: usize = 100;
+
+impl UndoLimit {
+    pub fn name(self) -> &'static str {
+        match self {
+            UndoLimit::Automatic => "Automatic",
+            UndoLimit::Unlimited => "Unlimited",
+            UndoLimit::Limited(_) => "Limited",
+        }
+    }
+
+    /// The maximum number of retained moves on a board of the given size, `None` if unlimited.
+    pub fn max_moves(self, size: u8) -> Option<usize> {
+        match self {
+            UndoLimit::Automatic if size <= AUTOMATIC_UNLIMITED_SIZE => None,
+            UndoLimit::Automatic => Some(AUTOMATIC_LIMIT),
+            UndoLimit::Unlimited => None,
+            UndoLimit::Limited(moves) => Some(moves),
+        }
+    }
+}
+
+/// The moves of the current game, used to rebuild earlier positions.
+///
+/// `hexgame` cannot take moves back, so undoing replays the retained moves on top of `base`.
+pub struct History {
+    /// The position before the first retained move. Moves dropped due to the limit are folded into it.
+    base: MctsHexGame,
+    moves: VecDeque<Move>,
+    max_moves: Option<usize>,
+    /// Moves folded into `base`. They cannot be undone, but are still part of the game record.
+    folded: Vec<Move>,
+    /// Undone moves that can be redone, the most recently undone last.
+    undone: Vec<Move>,
+}
+
+impl History {
+    /// Starts an empty history at `game`.
+    pub fn new(game: &MctsHexGame, max_moves: Option<usize>) -> Self {
+        Self {
+            base: game.clone(),
+            moves: VecDeque::new(),
+            max_moves,
+            folded: Vec::new(),
+            undone: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    pub fn last(&self) -> Option<Move> {
+        self.moves.back().copied()
+    }
+
+    /// The retained moves with their 1-based move numbers, oldest first.
+    pub fn numbered_moves(&self) -> impl Iterator<Item = (usize, &Move)> + '_ {
+        self.moves
+            .iter()
+            .enumerate()
+            .map(move |(index, m)| (self.folded.len() + index + 1, m))
+    }
+
+    /// Every move of the game, including those that can no longer be undone, oldest first.
+    pub fn all_moves(&self) -> impl Iterator<Item = &Move> + '_ {
+        self.folded.iter().chain(self.moves.iter())
+    }
+
+    /// Appends a move. Moves that were undone can no longer be redone afterwards.
+    pub fn record(&mut self, coords: Coords, color: Color) {
+        self.undone.clear();
+        self.moves.push_back(Move {
+            coords,
+            color,
+            blunder: None,
+            eval: None,
+            swap: false,
+        });
+        self.trim();
+    }
+
+    /// Appends a swap by `color`, which leaves a stone of that color on `coords`.
+    pub fn record_swap(&mut self, coords: Coords, color: Color) {
+        self.record(coords, color);
+        if let Some(last) = self.moves.back_mut() {
+            last.swap = true;
+        }
+    }
+
+    /// The retained move with the 1-based move number `number`, if it has not been undone.
+    pub fn move_mut(&mut self, number: usize) -> Option<&mut Move> {
+        let index = number.checked_sub(self.folded.len() + 1)?;
+        self.moves.get_mut(index)
+    }
+
+    /// Stores the mover's estimated win probability after the last move.
+    pub fn set_last_eval(&mut self, win_probability: f32) {
+        if let Some(last) = self.moves.back_mut() {
+            last.eval = Some(win_probability);
+        }
+    }
+
+    pub fn set_max_moves(&mut self, max_moves: Option<usize>) {
+        self.max_moves = max_moves;
+        self.trim();
+    }
+
+    /// Takes back the last move and returns the resulting position.
+    pub fn undo(&mut self) -> Option<MctsHexGame> {
+        let undone = self.moves.pop_back()?;
+        self.undone.push(undone);
+        Some(self.rebuild())
+    }
+
+    /// Plays the most recently undone move again and returns the resulting position.
+    pub fn redo(&mut self) -> Option<MctsHexGame> {
+        let redone = self.undone.pop()?;
+        self.moves.push_back(redone);
+        self.trim();
+        Some(self.rebuild())
+    }
+
+    /// Takes the stone on `coords` off the board and returns the resulting position.
+    ///
+    /// The move that placed it is forgotten. The side to move is the one that would follow the last
+    /// remaining move.
+    pub fn remove(&mut self, coords: Coords) -> MctsHexGame {
+        self.undone.clear();
+        match self.moves.iter().rposition(|m| m.coords == coords) {
+            Some(index) => {
+                self.moves.remove(index);
+            }
+            None => {
+                self.base = board::remove_stone(&self.base, coords);
+                if let Some(index) = self.folded.iter().rposition(|m| m.coords == coords) {
+                    self.folded.remove(index);
+                }
+            }
+        }
+        self.rebuild()
+    }
+
+    /// The position after the first `len` retained moves.
+    pub fn position_after(&self, len: usize) -> MctsHexGame {
+        let mut game = self.base.clone();
+        for m in self.moves.iter().take(len) {
+            m.apply(&mut game);
+        }
+        game
+    }
+
+    /// Forgets every retained move after the first `len` and returns the resulting position.
+    pub fn truncate(&mut self, len: usize) -> MctsHexGame {
+        self.undone.clear();
+        self.moves.truncate(len);
+        self.rebuild()
+    }
+
+    fn trim(&mut self) {
+        if let Some(max_moves) = self.max_moves {
+            while self.moves.len() > max_moves {
+                let oldest = self.moves.pop_front().expect("history is not empty");
+                oldest.apply(&mut self.base);
+                self.folded.push(oldest);
+            }
+        }
+    }
+
+    fn rebuild(&self) -> MctsHexGame {
+        self.position_after(self.moves.len())
+    }
+}
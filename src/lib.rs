@@ -1,7 +1,28 @@
 #![forbid(unsafe_code)]
 #![warn(clippy::all, rust_2018_idioms)]
 
+pub mod ai;
 mod app;
+pub mod board;
+mod console;
+mod drill;
+#[cfg(not(target_arch = "wasm32"))]
+mod game_log;
+mod geometry;
+pub mod headless;
+mod history;
+pub mod notation;
+mod openings;
+#[cfg(not(target_arch = "wasm32"))]
+mod oracle;
+#[cfg(not(target_arch = "wasm32"))]
+mod png_export;
+mod search;
+pub mod summary;
+pub mod svg;
+mod theme;
+#[cfg(target_arch = "wasm32")]
+mod web;
 pub use app::HexGameUi;
 
 // ----------------------------------------------------------------------------
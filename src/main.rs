@@ -5,6 +5,8 @@
 // When compiling natively:
 #[cfg(not(target_arch = "wasm32"))]
 fn main() {
+    #[cfg(feature = "ai-logging")]
+    env_logger::init();
     let app = hex_ui::HexGameUi::default();
     let native_options = eframe::NativeOptions::default();
     eframe::run_native(Box::new(app), native_options);
@@ -0,0 +1,174 @@
+use hexgame::{Board, Color, Coords};
+
+use crate::board::{to_xy, GameMove};
+
+/// How cells are written in labels, tooltips and move lists.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "persistence", derive(serde::Deserialize, serde::Serialize))]
+pub enum CoordinateSystem {
+    /// A column letter followed by a 1-based row number, e.g. `c3`. The letter follows the `x` axis
+    /// (along the top edge of the board), the number the `y` axis.
+    Letters,
+    /// The 0-based axial coordinates `x,y`, e.g. `2,2` for `c3`.
+    Axial,
+}
+
+impl CoordinateSystem {
+    pub fn name(self) -> &'static str {
+        match self {
+            CoordinateSystem::Letters => "Letter-number",
+            CoordinateSystem::Axial => "Axial",
+        }
+    }
+}
+
+pub fn format_coord(coords: Coords, system: CoordinateSystem) -> String {
+    let (x, y) = to_xy(coords);
+    match system {
+        CoordinateSystem::Letters => format!("{}{}", column_label(x, system), row_label(y, system)),
+        CoordinateSystem::Axial => format!("{},{}", x, y),
+    }
+}
+
+/// The part of [`format_coord`] that names the column `x`, as labeled along the top of the board.
+pub fn column_label(x: u8, system: CoordinateSystem) -> String {
+    match system {
+        CoordinateSystem::Letters => ((b'a' + x) as char).to_string(),
+        CoordinateSystem::Axial => x.to_string(),
+    }
+}
+
+/// The part of [`format_coord`] that names the row `y`, as labeled down the side of the board.
+pub fn row_label(y: u8, system: CoordinateSystem) -> String {
+    match system {
+        CoordinateSystem::Letters => (y + 1).to_string(),
+        CoordinateSystem::Axial => y.to_string(),
+    }
+}
+
+/// Parses a cell written in either coordinate system, detecting which one is used.
+///
+/// Only the syntax is checked, not whether the cell exists on a particular board.
+pub fn parse_coord(text: &str) -> Option<Coords> {
+    let text = text.trim();
+    if let Some((x, y)) = text.split_once(',') {
+        return Some(Coords::new(x.trim().parse().ok()?, y.trim().parse().ok()?));
+    }
+
+    let mut chars = text.chars();
+    let letter = chars.next()?.to_ascii_lowercase();
+    if !letter.is_ascii_lowercase() {
+        return None;
+    }
+    let number: u8 = chars.as_str().parse().ok()?;
+    Some(Coords::new(letter as u8 - b'a', number.checked_sub(1)?))
+}
+
+/// Formats a sequence of moves as space separated coordinates, e.g. `c3 d5 b2`.
+pub fn format_moves(moves: &[Coords], system: CoordinateSystem) -> String {
+    moves
+        .iter()
+        .map(|&coords| format_coord(coords, system))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The token of [`GameMove::Swap`] in [`format_game`].
+const SWAP_TOKEN: &str = "swap";
+
+/// Formats a game as its board size followed by its moves, e.g. `5 c3 d5 b2` or `5 c3 swap b2`.
+pub fn format_game(size: u8, moves: &[GameMove], system: CoordinateSystem) -> String {
+    let mut tokens = vec![size.to_string()];
+    tokens.extend(moves.iter().map(|&game_move| match game_move {
+        GameMove::Stone(coords) => format_coord(coords, system),
+        GameMove::Swap => SWAP_TOKEN.to_owned(),
+    }));
+    tokens.join(" ")
+}
+
+/// Parses the output of [`format_game`] in either coordinate system into the board size and moves.
+///
+/// Whether the moves can actually be played is left to [`crate::board::replay`].
+pub fn parse_game(text: &str) -> Result<(u8, Vec<GameMove>), String> {
+    let mut tokens = text.split_whitespace();
+    let size = tokens.next().ok_or("The game is empty")?;
+    let size = size
+        .parse()
+        .map_err(|_| format!("\"{}\" is not a board size", size))?;
+    let moves = tokens
+        .enumerate()
+        .map(|(index, token)| {
+            if token.eq_ignore_ascii_case(SWAP_TOKEN) {
+                return Ok(GameMove::Swap);
+            }
+            parse_coord(token)
+                .map(GameMove::Stone)
+                .ok_or_else(|| format!("Move {} \"{}\" is not a coordinate", index + 1, token))
+        })
+        .collect::<Result<_, _>>()?;
+    Ok((size, moves))
+}
+
+/// Renders `board` as text, one line per `y` coordinate.
+///
+/// Every line is indented by one more space than the one before, so the output has the same
+/// rhombus shape as the board on screen. Red stones are `R`, blue stones `B` and empty cells `.`.
+pub fn board_to_ascii(board: &Board) -> String {
+    let size = board.size();
+    let mut ascii = String::new();
+    for y in 0..size {
+        ascii.extend(std::iter::repeat(' ').take(y as usize));
+        let cells: Vec<_> = (0..size)
+            .map(|x| match board.get_color(Coords::new(x, y)) {
+                Some(Color::Black) => "R",
+                Some(Color::White) => "B",
+                None => ".",
+            })
+            .collect();
+        ascii.push_str(&cells.join(" "));
+        ascii.push('\n');
+    }
+    ascii
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board;
+
+    fn parse_xy(text: &str) -> Option<(u8, u8)> {
+        parse_coord(text).map(to_xy)
+    }
+
+    #[test]
+    fn parse_coord_handles_edge_cases() {
+        assert_eq!(parse_xy("C3"), Some((2, 2)));
+        assert_eq!(parse_xy(" c3 "), Some((2, 2)));
+        assert_eq!(parse_xy("b12"), Some((1, 11)));
+        assert_eq!(parse_xy("2, 3"), Some((2, 3)));
+        // Rows are 1-based.
+        assert_eq!(parse_xy("a0"), None);
+        for invalid in ["", "a", "3c", "a-1", "a256", "ä1", "2,"] {
+            assert_eq!(parse_xy(invalid), None, "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn cells_past_the_board_parse_but_do_not_replay() {
+        let coords = parse_coord("z1").expect("z is a column letter");
+        assert_eq!(to_xy(coords), (25, 0));
+        assert!(board::replay(5, &[GameMove::Stone(coords)]).is_err());
+        assert!(board::replay(5, &[GameMove::Stone(parse_coord("f1").unwrap())]).is_err());
+        assert!(board::replay(5, &[GameMove::Stone(parse_coord("e6").unwrap())]).is_err());
+    }
+
+    #[test]
+    fn coordinates_round_trip() {
+        for system in [CoordinateSystem::Letters, CoordinateSystem::Axial] {
+            for (x, y) in [(0, 0), (4, 11), (16, 16)] {
+                let text = format_coord(Coords::new(x, y), system);
+                assert_eq!(parse_xy(&text), Some((x, y)), "{}", text);
+            }
+        }
+    }
+}
@@ -0,0 +1,68 @@
+//! Named openings to start games from, for studying them.
+
+use std::collections::HashMap;
+
+use hexgame::Coords;
+
+use crate::{board, notation};
+
+/// Board size, name and moves of every shipped opening. The moves are written in a notation
+/// [`notation::parse_coord`] accepts.
+const OPENINGS: &[(u8, &str, &str)] = &[
+    (7, "Center", "d4"),
+    (7, "Center, answered next to it", "d4 c5"),
+    (7, "Edge", "a4"),
+    (9, "Center", "e5"),
+    (9, "Center, answered opposite", "e5 d6"),
+    (9, "Acute corner", "a1"),
+    (11, "Center", "f6"),
+    (11, "a3", "a3"),
+    (11, "Short diagonal", "c9"),
+    (13, "Center", "g7"),
+    (13, "a3", "a3"),
+];
+
+/// The shipped openings by board size, each with its name and moves.
+///
+/// Openings that cannot be replayed on their board are left out.
+pub fn openings() -> HashMap<u8, Vec<(String, Vec<Coords>)>> {
+    let mut openings: HashMap<u8, Vec<_>> = HashMap::new();
+    for &(size, name, moves) in OPENINGS {
+        let moves: Option<Vec<_>> = moves
+            .split_whitespace()
+            .map(notation::parse_coord)
+            .collect();
+        let replays = |moves: &Vec<Coords>| {
+            let moves: Vec<_> = moves.iter().copied().map(board::GameMove::Stone).collect();
+            board::replay(size, &moves).is_ok()
+        };
+        if let Some(moves) = moves.filter(replays) {
+            openings
+                .entry(size)
+                .or_default()
+                .push((name.to_owned(), moves));
+        }
+    }
+    openings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_opening_replays_on_its_size() {
+        let openings = openings();
+        for &(size, name, _) in OPENINGS {
+            assert!(
+                openings.get(&size).map_or(false, |openings| openings
+                    .iter()
+                    .any(|(opening, _)| opening == name)),
+                "{} on {}x{} does not replay",
+                name,
+                size,
+                size
+            );
+        }
+    }
+}
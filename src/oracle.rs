@@ -0,0 +1,114 @@
+//! Perfect play on tiny boards, found by searching every continuation.
+
+use std::collections::HashMap;
+
+use hexgame::{Coords, Status};
+use hexgame_ai::MctsHexGame;
+
+use crate::board;
+
+/// The largest board the oracle solves. Beyond this, solving takes too long.
+pub const MAX_ORACLE_SIZE: u8 = 4;
+
+/// For every empty cell, whether the side to move wins with perfect play after playing there.
+///
+/// Returns `None` on boards larger than [`MAX_ORACLE_SIZE`] and in finished games.
+pub fn solve(game: &MctsHexGame) -> Option<Vec<(Coords, bool)>> {
+    let size = game.game.board.size();
+    if size > MAX_ORACLE_SIZE || !matches!(game.game.status, Status::Ongoing) {
+        return None;
+    }
+    let mut solved = HashMap::new();
+    Some(
+        empty_cells(game)
+            .map(|coords| (coords, wins_with(game, coords, &mut solved)))
+            .collect(),
+    )
+}
+
+/// Whether the side to move in the ongoing `game` wins with perfect play. Hex has no draws, so
+/// otherwise the opponent wins.
+///
+/// `solved` remembers the result for every position searched so far, by [`board::position_key`].
+fn side_to_move_wins(game: &MctsHexGame, solved: &mut HashMap<u64, bool>) -> bool {
+    let key = board::position_key(game);
+    if let Some(&wins) = solved.get(&key) {
+        return wins;
+    }
+    let wins = empty_cells(game).any(|coords| wins_with(game, coords, solved));
+    solved.insert(key, wins);
+    wins
+}
+
+/// Whether the side to move in `game` wins with perfect play after playing `coords`.
+fn wins_with(game: &MctsHexGame, coords: Coords, solved: &mut HashMap<u64, bool>) -> bool {
+    let player = game.game.current_player;
+    let mut after = game.clone();
+    board::place_stone(&mut after, coords, player);
+    match after.game.status {
+        Status::Finished(winner) => winner == player,
+        Status::Ongoing => !side_to_move_wins(&after, solved),
+    }
+}
+
+fn empty_cells(game: &MctsHexGame) -> impl Iterator<Item = Coords> + '_ {
+    let board = &game.game.board;
+    let size = board.size();
+    (0..size)
+        .flat_map(move |x| (0..size).map(move |y| Coords::new(x, y)))
+        .filter(move |&coords| board.get_color(coords).is_none())
+}
+
+#[cfg(test)]
+mod tests {
+    use hexgame::Color;
+
+    use super::*;
+
+    fn verdicts(game: &MctsHexGame) -> HashMap<(u8, u8), bool> {
+        solve(game)
+            .expect("tiny boards are solved")
+            .into_iter()
+            .map(|(coords, wins)| (board::to_xy(coords), wins))
+            .collect()
+    }
+
+    #[test]
+    fn short_diagonal_wins_on_2x2() {
+        let verdicts = verdicts(&board::empty_game(2));
+        // A stone on the short diagonal has two neighbors on the edge it does not touch yet.
+        assert!(verdicts[&(1, 0)]);
+        assert!(verdicts[&(0, 1)]);
+        assert!(!verdicts[&(0, 0)]);
+        assert!(!verdicts[&(1, 1)]);
+    }
+
+    #[test]
+    fn center_wins_on_3x3() {
+        assert!(verdicts(&board::empty_game(3))[&(1, 1)]);
+    }
+
+    /// The verdicts are for the side to move, so handing the position to the other side keeps
+    /// them once the board is mirrored along with the colors, see [`board::swap_sides`].
+    #[test]
+    fn swapping_sides_keeps_every_verdict() {
+        let mut game = board::empty_game(3);
+        board::place_stone(&mut game, Coords::new(0, 1), Color::Black);
+        board::place_stone(&mut game, Coords::new(2, 0), Color::White);
+        let black = verdicts(&game);
+        let white = verdicts(&board::swap_sides(&game));
+        assert_eq!(black.len(), 7);
+        for (&(x, y), &wins) in &black {
+            assert_eq!(white[&(y, x)], wins, "{:?}", (x, y));
+        }
+    }
+
+    #[test]
+    fn large_and_finished_games_are_not_solved() {
+        assert!(solve(&board::empty_game(MAX_ORACLE_SIZE + 1)).is_none());
+        let mut game = board::empty_game(2);
+        board::place_stone(&mut game, Coords::new(0, 0), Color::Black);
+        board::place_stone(&mut game, Coords::new(1, 0), Color::Black);
+        assert!(solve(&game).is_none());
+    }
+}
@@ -0,0 +1,178 @@
+//! Renders positions to PNG files with a small software rasterizer.
+
+use std::{
+    fs::File,
+    io::{self, BufWriter},
+    path::Path,
+};
+
+use hexgame::{Board, Color, Coords};
+
+use crate::{
+    board::{self, Axis},
+    geometry::{self, HEX_CORNERS, HEX_RADIUS},
+};
+
+type Rgba = [u8; 4];
+
+const RED: Rgba = [255, 0, 0, 255];
+const BLUE: Rgba = [0, 0, 255, 255];
+const EMPTY: Rgba = [160, 160, 160, 255];
+const OUTLINE: Rgba = [96, 96, 96, 255];
+
+/// Empty space around the board, in points.
+const MARGIN: f32 = 10.0;
+const EDGE_WIDTH: f32 = 4.0;
+const OUTLINE_WIDTH: f32 = 1.0;
+
+/// An RGBA image, row by row.
+struct Image {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * 4],
+        }
+    }
+
+    /// Calls `shade` for every pixel in the given range of the image, with the pixel center in
+    /// image coordinates, and sets the pixel to the color it returns.
+    fn fill(
+        &mut self,
+        (min_x, min_y, max_x, max_y): (f32, f32, f32, f32),
+        shade: impl Fn(f32, f32) -> Option<Rgba>,
+    ) {
+        let clamp = |value: f32, limit: u32| (value.max(0.0) as u32).min(limit);
+        for y in clamp(min_y, self.height)..clamp(max_y.ceil(), self.height) {
+            for x in clamp(min_x, self.width)..clamp(max_x.ceil(), self.width) {
+                if let Some(color) = shade(x as f32 + 0.5, y as f32 + 0.5) {
+                    let index = (y as usize * self.width as usize + x as usize) * 4;
+                    self.pixels[index..index + 4].copy_from_slice(&color);
+                }
+            }
+        }
+    }
+}
+
+/// How far `(x, y)` lies inside the hex with the given radius centered on the origin. Negative
+/// outside of the hex.
+fn hex_depth(x: f32, y: f32, radius: f32) -> f32 {
+    (0..HEX_CORNERS.len())
+        .map(|i| {
+            let (ax, ay) = HEX_CORNERS[i];
+            let (bx, by) = HEX_CORNERS[(i + 1) % HEX_CORNERS.len()];
+            (by - ay) * (x - ax * radius) - (bx - ax) * (y - ay * radius)
+        })
+        .fold(f32::MAX, f32::min)
+}
+
+/// Distance of `(x, y)` from the line segment between `from` and `to`.
+fn segment_distance((x, y): (f32, f32), from: (f32, f32), to: (f32, f32)) -> f32 {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let length_sq = dx * dx + dy * dy;
+    let t = if length_sq == 0.0 {
+        0.0
+    } else {
+        (((x - from.0) * dx + (y - from.1) * dy) / length_sq).clamp(0.0, 1.0)
+    };
+    let (closest_x, closest_y) = (from.0 + t * dx, from.1 + t * dy);
+    ((x - closest_x).powi(2) + (y - closest_y).powi(2)).sqrt()
+}
+
+fn render(board: &Board, scale: f32) -> Image {
+    let size = board.size();
+    let (min_x, min_y, max_x, max_y) = geometry::drawing_bounds(size);
+    let mut image = Image::new(
+        ((max_x - min_x + 2.0 * MARGIN) * scale).ceil() as u32,
+        ((max_y - min_y + 2.0 * MARGIN) * scale).ceil() as u32,
+    );
+    // Maps an offset from the center of cell `(0, 0)` to image coordinates.
+    let to_image =
+        |(x, y): (f32, f32)| ((x - min_x + MARGIN) * scale, (y - min_y + MARGIN) * scale);
+
+    let [top_left, top_right, bottom_left, bottom_right] =
+        geometry::edge_corners(size).map(to_image);
+    let half_width = EDGE_WIDTH * scale * 0.5;
+    for color in [Color::Black, Color::White] {
+        let edges = match board::goal_axis(color) {
+            Axis::X => [(top_left, bottom_left), (top_right, bottom_right)],
+            Axis::Y => [(top_left, top_right), (bottom_left, bottom_right)],
+        };
+        for (from, to) in edges {
+            let bounds = (
+                from.0.min(to.0) - half_width,
+                from.1.min(to.1) - half_width,
+                from.0.max(to.0) + half_width,
+                from.1.max(to.1) + half_width,
+            );
+            let fill = color_fill(Some(color));
+            image.fill(bounds, |x, y| {
+                Some(fill).filter(|_| segment_distance((x, y), from, to) <= half_width)
+            });
+        }
+    }
+
+    let radius = HEX_RADIUS * scale;
+    for x in 0..size {
+        for y in 0..size {
+            let (center_x, center_y) = to_image(geometry::board_point(x as f32, y as f32));
+            let fill = color_fill(board.get_color(Coords::new(x, y)));
+            let bounds = (
+                center_x - radius,
+                center_y - radius,
+                center_x + radius,
+                center_y + radius,
+            );
+            image.fill(bounds, |px, py| {
+                let depth = hex_depth(px - center_x, py - center_y, radius);
+                if depth < 0.0 {
+                    None
+                } else if depth < OUTLINE_WIDTH * scale {
+                    Some(OUTLINE)
+                } else {
+                    Some(fill)
+                }
+            });
+        }
+    }
+
+    image
+}
+
+/// The color used for cells of the given color, matching the colors on screen.
+fn color_fill(color: Option<Color>) -> Rgba {
+    match color {
+        Some(Color::Black) => RED,
+        Some(Color::White) => BLUE,
+        None => EMPTY,
+    }
+}
+
+/// Writes `board` with its goal edges to a PNG file at `path`.
+///
+/// `scale` is the number of pixels per point of the on-screen board. A `title`, e.g. the game ID, is
+/// stored in the file's metadata.
+pub fn save_png(path: &Path, board: &Board, scale: f32, title: Option<&str>) -> io::Result<()> {
+    let image = render(board, scale);
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = png::Encoder::new(file, image.width, image.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let to_io_error = |error: png::EncodingError| io::Error::new(io::ErrorKind::Other, error);
+    if let Some(title) = title {
+        encoder
+            .add_text_chunk("Title".to_owned(), title.to_owned())
+            .map_err(to_io_error)?;
+    }
+    encoder
+        .write_header()
+        .map_err(to_io_error)?
+        .write_image_data(&image.pixels)
+        .map_err(to_io_error)
+}
@@ -0,0 +1,390 @@
+//! The Monte Carlo tree search behind the AI.
+//!
+//! It works like the `mcts` setup the AI started out with: UCT selection, full expansion, shuffled
+//! playouts and the most visited move at the root. Living here, it can stop on the clock between
+//! two iterations, change the exploration between iterations and bias selection and playouts by the
+//! AI's [`Personality`], which `mcts` cannot.
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{Duration, Instant};
+
+use hexgame::{Color, Coords, Status};
+use hexgame_ai::MctsHexGame;
+use rand::{prelude::SmallRng, seq::SliceRandom};
+
+use crate::{ai::Personality, board};
+
+/// The bias a move that suits the personality perfectly gets in the selection, before its first
+/// visit. It fades with every visit.
+const PERSONALITY_WEIGHT: f32 = 0.5;
+
+/// How many random cells a playout compares when it picks a move of the searching player with a
+/// personality. The one that suits the personality best is played.
+const PLAYOUT_CHOICES: usize = 3;
+
+/// When a search stops.
+#[derive(Clone, Copy, Debug)]
+pub struct Limits {
+    /// The most iterations to run.
+    pub iterations: u32,
+    /// Stop once this much time has passed, even if iterations are left. The iteration running at
+    /// that moment is finished first.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub time: Option<Duration>,
+}
+
+/// The exploration parameter of the UCT selection, which moves from `start` at the first iteration
+/// to `end` when the search stops.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Exploration {
+    pub start: f32,
+    pub end: f32,
+}
+
+impl Exploration {
+    /// The exploration parameter once the search has used up the fraction `progress` of its limits.
+    fn at(self, progress: f32) -> f32 {
+        self.start + (self.end - self.start) * progress.clamp(0.0, 1.0)
+    }
+}
+
+/// What a search found out about one move at the root.
+#[derive(Clone, Copy, Debug)]
+pub struct RootChild {
+    pub action: Coords,
+    pub visits: u32,
+    /// Playouts through `action` won by the player to move at the root.
+    pub wins: u32,
+}
+
+/// The outcome of [`search`].
+#[derive(Clone, Debug)]
+pub struct SearchResult {
+    /// Every move of the root, most visited first.
+    pub children: Vec<RootChild>,
+    /// The number of iterations that ran.
+    pub iterations: u32,
+}
+
+struct Node {
+    /// The move that leads here from the parent, `None` for the root.
+    action: Option<Coords>,
+    /// The player who played `action`.
+    player: Color,
+    visits: u32,
+    /// Playouts through this node won by `player`.
+    wins: u32,
+    /// How well `action` suits the personality, if `player` is the one searching, see
+    /// [`Personality::preference`].
+    preference: f32,
+    children: Vec<usize>,
+    expanded: bool,
+}
+
+impl Node {
+    fn new(action: Option<Coords>, player: Color, preference: f32) -> Self {
+        Self {
+            action,
+            player,
+            visits: 0,
+            wins: 0,
+            preference,
+            children: Vec::new(),
+            expanded: false,
+        }
+    }
+}
+
+/// What stays the same during a search.
+struct Searcher {
+    personality: Personality,
+    /// The player to move at the root, whose moves follow `personality`.
+    player: Color,
+}
+
+/// Searches `game` for the player to move until `limits` are reached, with UCT selection using
+/// `exploration`, biased towards the moves that suit `personality`.
+///
+/// The exploration parameter follows whichever limit is closer to being reached, so it arrives at
+/// `exploration.end` as the search stops.
+///
+/// At least one iteration is always run, so the root's moves are known even if the time is up
+/// before the search starts. Returns `None` if the game is over.
+pub fn search(
+    game: &MctsHexGame,
+    exploration: Exploration,
+    personality: Personality,
+    limits: Limits,
+    rng: &mut SmallRng,
+) -> Option<SearchResult> {
+    if !matches!(game.game.status, Status::Ongoing) {
+        return None;
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    let started = Instant::now();
+    let searcher = Searcher {
+        personality,
+        player: game.game.current_player,
+    };
+    let mut nodes = vec![Node::new(None, board::opponent(searcher.player), 0.0)];
+    let mut iterations = 0;
+    let mut progress = 0.0;
+    loop {
+        searcher.iterate(game, &mut nodes, exploration.at(progress), rng);
+        iterations += 1;
+        if iterations >= limits.iterations {
+            break;
+        }
+        progress = iterations as f32 / limits.iterations as f32;
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(time) = limits.time {
+            let elapsed = started.elapsed();
+            if elapsed >= time {
+                break;
+            }
+            progress = progress.max(elapsed.as_secs_f32() / time.as_secs_f32());
+        }
+    }
+
+    let mut children: Vec<_> = nodes[0]
+        .children
+        .iter()
+        .filter_map(|&child| {
+            let node = &nodes[child];
+            Some(RootChild {
+                action: node.action?,
+                visits: node.visits,
+                wins: node.wins,
+            })
+        })
+        .collect();
+    children.sort_by(|a, b| b.visits.cmp(&a.visits));
+    Some(SearchResult {
+        children,
+        iterations,
+    })
+}
+
+impl Searcher {
+    /// Runs one iteration: selects a path down the tree, expands its last node, plays a random game
+    /// from there and counts the result along the path.
+    fn iterate(
+        &self,
+        root: &MctsHexGame,
+        nodes: &mut Vec<Node>,
+        exploration_parameter: f32,
+        rng: &mut SmallRng,
+    ) {
+        let mut game = root.clone();
+        let mut path = vec![0];
+        let mut current = 0;
+        loop {
+            if !matches!(game.game.status, Status::Ongoing) {
+                break;
+            }
+            if !nodes[current].expanded {
+                self.expand(&game, nodes, current, rng);
+            }
+            let child = match self.select(nodes, current, exploration_parameter) {
+                Some(child) => child,
+                None => break,
+            };
+            let first_visit = nodes[child].visits == 0;
+            if let Some(action) = nodes[child].action {
+                if game.play(action).is_err() {
+                    break;
+                }
+            }
+            path.push(child);
+            current = child;
+            if first_visit {
+                break;
+            }
+        }
+
+        let winner = self.playout(&mut game, rng);
+        for &node in &path {
+            let node = &mut nodes[node];
+            node.visits += 1;
+            if winner == Some(node.player) {
+                node.wins += 1;
+            }
+        }
+    }
+
+    /// Adds a child to `parent` for every empty cell of `game`, in random order, so ties in the
+    /// selection are broken randomly.
+    fn expand(&self, game: &MctsHexGame, nodes: &mut Vec<Node>, parent: usize, rng: &mut SmallRng) {
+        let player = game.game.current_player;
+        let mut cells = empty_cells(game);
+        cells.shuffle(rng);
+        for coords in cells {
+            let preference = if player == self.player {
+                self.personality.preference(game, coords)
+            } else {
+                0.0
+            };
+            nodes.push(Node::new(Some(coords), player, preference));
+            let child = nodes.len() - 1;
+            nodes[parent].children.push(child);
+        }
+        nodes[parent].expanded = true;
+    }
+
+    /// The child of `parent` with the highest UCT value plus personality bias. Unvisited children
+    /// come first, the preferred ones among them.
+    fn select(&self, nodes: &[Node], parent: usize, exploration_parameter: f32) -> Option<usize> {
+        let log_visits = (nodes[parent].visits.max(1) as f32).ln();
+        // Whether the child is unvisited comes first, so unvisited children beat any value.
+        let value = |child: usize| {
+            let node = &nodes[child];
+            let bias = PERSONALITY_WEIGHT * node.preference / (node.visits + 1) as f32;
+            if node.visits == 0 {
+                return (true, bias);
+            }
+            let visits = node.visits as f32;
+            let uct =
+                node.wins as f32 / visits + exploration_parameter * (log_visits / visits).sqrt();
+            (false, uct + bias)
+        };
+        nodes[parent]
+            .children
+            .iter()
+            .copied()
+            .fold(None, |best: Option<(usize, (bool, f32))>, child| {
+                let value = value(child);
+                match best {
+                    Some((_, best_value)) if best_value >= value => best,
+                    _ => Some((child, value)),
+                }
+            })
+            .map(|(child, _)| child)
+    }
+
+    /// Fills the empty cells of `game` in random order until someone wins, and returns the winner.
+    ///
+    /// The searching player picks the cell that suits the personality best among the next few.
+    fn playout(&self, game: &mut MctsHexGame, rng: &mut SmallRng) -> Option<Color> {
+        let mut cells = empty_cells(game);
+        cells.shuffle(rng);
+        for next in 0..cells.len() {
+            if let Status::Finished(winner) = game.game.status {
+                return Some(winner);
+            }
+            if self.personality != Personality::Balanced && game.game.current_player == self.player
+            {
+                let (preferred, _) = (next..cells.len().min(next + PLAYOUT_CHOICES))
+                    .map(|index| (index, self.personality.preference(game, cells[index])))
+                    .fold((next, f32::MIN), |best, choice| {
+                        if choice.1 > best.1 {
+                            choice
+                        } else {
+                            best
+                        }
+                    });
+                cells.swap(next, preferred);
+            }
+            game.play(cells[next]).ok()?;
+        }
+        match game.game.status {
+            Status::Finished(winner) => Some(winner),
+            Status::Ongoing => None,
+        }
+    }
+}
+
+fn empty_cells(game: &MctsHexGame) -> Vec<Coords> {
+    let board = &game.game.board;
+    let size = board.size();
+    (0..size)
+        .flat_map(|x| (0..size).map(move |y| Coords::new(x, y)))
+        .filter(|&coords| board.get_color(coords).is_none())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::board::{test_game, to_xy};
+
+    const EXPLORATION: Exploration = Exploration {
+        start: 0.5,
+        end: 0.5,
+    };
+
+    #[test]
+    fn exploration_moves_from_start_to_end() {
+        let exploration = Exploration {
+            start: 1.0,
+            end: 0.2,
+        };
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-6;
+        assert!(close(exploration.at(0.0), 1.0));
+        assert!(close(exploration.at(0.5), 0.6));
+        assert!(close(exploration.at(1.0), 0.2));
+        assert!(close(exploration.at(2.0), 0.2));
+    }
+
+    #[test]
+    fn search_stops_at_the_iteration_limit() {
+        let game = test_game(4, &[(1, 1)]);
+        let limits = Limits {
+            iterations: 300,
+            #[cfg(not(target_arch = "wasm32"))]
+            time: None,
+        };
+        let mut rng = SmallRng::seed_from_u64(3);
+
+        let result = search(&game, EXPLORATION, Personality::Balanced, limits, &mut rng)
+            .expect("the game is ongoing");
+        assert_eq!(result.iterations, 300);
+        assert_eq!(result.children.len(), 15);
+        let visits: u32 = result.children.iter().map(|child| child.visits).sum();
+        assert_eq!(visits, 300);
+        assert!(result
+            .children
+            .windows(2)
+            .all(|w| w[0].visits >= w[1].visits));
+    }
+
+    #[test]
+    fn personality_tries_its_preferred_move_first() {
+        let game = test_game(5, &[]);
+        let limits = Limits {
+            iterations: 1,
+            #[cfg(not(target_arch = "wasm32"))]
+            time: None,
+        };
+        let mut rng = SmallRng::seed_from_u64(3);
+
+        let result = search(
+            &game,
+            EXPLORATION,
+            Personality::Aggressive,
+            limits,
+            &mut rng,
+        )
+        .expect("the game is ongoing");
+        assert_eq!(to_xy(result.children[0].action), (2, 2));
+        assert_eq!(result.children[0].visits, 1);
+    }
+
+    #[test]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn search_stops_on_the_clock() {
+        let game = test_game(11, &[]);
+        let limits = Limits {
+            iterations: u32::MAX,
+            time: Some(Duration::from_millis(50)),
+        };
+        let mut rng = SmallRng::seed_from_u64(3);
+
+        let started = Instant::now();
+        let result = search(&game, EXPLORATION, Personality::Balanced, limits, &mut rng)
+            .expect("the game is ongoing");
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(result.iterations >= 1);
+    }
+}
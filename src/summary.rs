@@ -0,0 +1,115 @@
+//! Summaries of games as JSON, for archiving and analyzing them in other tools.
+
+use hexgame::{Color, Coords};
+
+use crate::{
+    board,
+    notation::{self, CoordinateSystem},
+};
+
+/// One move of a [`GameSummary`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MoveSummary {
+    pub coords: Coords,
+    pub color: Color,
+    /// The mover's estimated win probability after the move, if the AI rated it.
+    pub eval: Option<f32>,
+}
+
+/// How a finished game ended.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GameResult {
+    pub winner: Color,
+    /// Whether the loser resigned instead of being cut off.
+    pub resigned: bool,
+}
+
+/// Everything [`summary_to_json`] writes about a game.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameSummary {
+    pub size: u8,
+    /// Names of the players, indexed by [`board::color_index`].
+    pub players: [String; 2],
+    pub moves: Vec<MoveSummary>,
+    /// `None` while the game is ongoing.
+    pub result: Option<GameResult>,
+    /// Start and end of the game in seconds since the Unix epoch, if known.
+    pub started: Option<u64>,
+    pub finished: Option<u64>,
+}
+
+/// `text` as a JSON string literal.
+fn json_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn json_option<T>(value: Option<T>, format: impl Fn(T) -> String) -> String {
+    value.map_or_else(|| "null".to_owned(), format)
+}
+
+/// Writes `summary` as a JSON document. Cells are written in the letter notation, players by their
+/// color names.
+pub fn summary_to_json(summary: &GameSummary) -> String {
+    let color = |color| json_string(board::color_name(color));
+    let moves: Vec<_> = summary
+        .moves
+        .iter()
+        .map(|m| {
+            format!(
+                r#"    {{"cell": {}, "player": {}, "eval": {}}}"#,
+                json_string(&notation::format_coord(m.coords, CoordinateSystem::Letters)),
+                color(m.color),
+                json_option(m.eval, |eval| format!("{:.3}", eval))
+            )
+        })
+        .collect();
+    let result = json_option(summary.result, |result| {
+        format!(
+            r#"{{"winner": {}, "resigned": {}}}"#,
+            color(result.winner),
+            result.resigned
+        )
+    });
+
+    let mut lines = vec![
+        "{".to_owned(),
+        format!(r#"  "size": {},"#, summary.size),
+        format!(
+            r#"  "players": {{{}: {}, {}: {}}},"#,
+            color(Color::Black),
+            json_string(&summary.players[board::color_index(Color::Black)]),
+            color(Color::White),
+            json_string(&summary.players[board::color_index(Color::White)])
+        ),
+    ];
+    if moves.is_empty() {
+        lines.push(r#"  "moves": [],"#.to_owned());
+    } else {
+        lines.push(r#"  "moves": ["#.to_owned());
+        lines.push(moves.join(",\n"));
+        lines.push("  ],".to_owned());
+    }
+    lines.push(format!(r#"  "result": {},"#, result));
+    lines.push(format!(
+        r#"  "started": {},"#,
+        json_option(summary.started, |time| time.to_string())
+    ));
+    lines.push(format!(
+        r#"  "finished": {}"#,
+        json_option(summary.finished, |time| time.to_string())
+    ));
+    lines.push("}\n".to_owned());
+    lines.join("\n")
+}
@@ -0,0 +1,113 @@
+//! Renders positions to SVG, for embedding them in articles.
+
+use hexgame::{Board, Color, Coords};
+
+use crate::{
+    board::{self, Axis},
+    geometry::{self, HEX_CORNERS, HEX_RADIUS},
+    notation::{self, CoordinateSystem},
+};
+
+/// What [`board_to_svg`] draws besides the cells.
+#[derive(Clone, Debug)]
+pub struct SvgOptions {
+    /// Label every cell with its coordinates in this system.
+    pub coordinates: Option<CoordinateSystem>,
+    /// Stored as the document's title, e.g. the game ID.
+    pub title: Option<String>,
+    /// Empty space around the board, in points.
+    pub margin: f32,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            coordinates: None,
+            title: None,
+            margin: 10.0,
+        }
+    }
+}
+
+/// The fill used for cells of the given color, matching the colors on screen.
+fn fill(color: Option<Color>) -> &'static str {
+    match color {
+        Some(Color::Black) => "#ff0000",
+        Some(Color::White) => "#0000ff",
+        None => "#a0a0a0",
+    }
+}
+
+/// Renders `board` with its goal edges as a standalone SVG document.
+pub fn board_to_svg(board: &Board, options: &SvgOptions) -> String {
+    let size = board.size();
+    let cells: Vec<_> = (0..size)
+        .flat_map(|x| (0..size).map(move |y| (x, y)))
+        .collect();
+
+    let (min_x, min_y, max_x, max_y) = geometry::drawing_bounds(size);
+    let margin = options.margin;
+    let (origin_x, origin_y) = (margin - min_x, margin - min_y);
+    let width = max_x - min_x + 2.0 * margin;
+    let height = max_y - min_y + 2.0 * margin;
+
+    let mut lines = Vec::new();
+    lines.push(format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{:.1}" height="{:.1}" viewBox="0 0 {:.1} {:.1}">"#,
+        width, height, width, height
+    ));
+    if let Some(title) = &options.title {
+        lines.push(format!("<title>{}</title>", title));
+    }
+
+    let [top_left, top_right, bottom_left, bottom_right] = geometry::edge_corners(size);
+    for color in [Color::Black, Color::White] {
+        let edges = match board::goal_axis(color) {
+            Axis::X => [(top_left, bottom_left), (top_right, bottom_right)],
+            Axis::Y => [(top_left, top_right), (bottom_left, bottom_right)],
+        };
+        for ((from_x, from_y), (to_x, to_y)) in edges {
+            lines.push(format!(
+                r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="{}" stroke-width="4"/>"#,
+                origin_x + from_x,
+                origin_y + from_y,
+                origin_x + to_x,
+                origin_y + to_y,
+                fill(Some(color))
+            ));
+        }
+    }
+
+    for &(x, y) in &cells {
+        let (center_x, center_y) = geometry::board_point(x as f32, y as f32);
+        let (center_x, center_y) = (origin_x + center_x, origin_y + center_y);
+        let points: Vec<_> = HEX_CORNERS
+            .iter()
+            .map(|&(dx, dy)| {
+                format!(
+                    "{:.1},{:.1}",
+                    center_x + dx * HEX_RADIUS,
+                    center_y + dy * HEX_RADIUS
+                )
+            })
+            .collect();
+        let coords = Coords::new(x, y);
+        lines.push(format!(
+            r##"<polygon points="{}" fill="{}" stroke="#606060" stroke-width="1"/>"##,
+            points.join(" "),
+            fill(board.get_color(coords))
+        ));
+
+        if let Some(system) = options.coordinates {
+            lines.push(format!(
+                r#"<text x="{:.1}" y="{:.1}" font-family="sans-serif" font-size="12" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+                center_x,
+                center_y,
+                notation::format_coord(coords, system)
+            ));
+        }
+    }
+
+    lines.push("</svg>\n".to_owned());
+    lines.join("\n")
+}
@@ -0,0 +1,107 @@
+//! Colors and line widths of the board, in one place so they can be swapped as a whole.
+
+use eframe::egui::{Color32, Stroke};
+use hexgame::Color;
+
+use crate::board;
+
+/// How the board and everything drawn on it looks.
+#[derive(Clone, Copy, Debug)]
+pub struct Theme {
+    /// Stones, goal edges and labels of each player, indexed by [`board::color_index`].
+    pub players: [Color32; 2],
+    pub empty_cell: Color32,
+    /// Outline of every cell.
+    pub grid: Stroke,
+    pub edge_width: f32,
+    /// Width of the hovered cell's outline, which has the color of the side to move.
+    pub hover_width: f32,
+    pub bridge_width: f32,
+    pub candidate_text: Color32,
+    /// Outline of a board of the size being picked, drawn over the current board.
+    pub size_overlay: Stroke,
+    /// Marks cells that win with perfect play, see [`crate::oracle::solve`].
+    pub oracle_win: Color32,
+    pub oracle_loss: Color32,
+    /// The flash on a cell a click was rejected on.
+    pub rejected_fill: Color32,
+    pub rejected_outline: Stroke,
+    pub hint: Stroke,
+    /// Outline of the last move of an opening drill that matched the opening.
+    pub drill_correct: Stroke,
+    /// Width of the flashing outline on the cell the AI is about to play.
+    pub preview_width: f32,
+    /// The line along the winning connection.
+    pub winning_path: Color32,
+    /// Outline of every cell of the winning connection.
+    pub winning_cells: Stroke,
+    /// Marks the last moves: a dot on the human's, a ring of the given width on the AI's. Its size is
+    /// a fraction of the hex radius.
+    pub last_move: Color32,
+    pub last_move_size: f32,
+    pub last_move_width: f32,
+    /// The column and row labels around the board.
+    pub coordinate_text: Color32,
+    pub debug_text: Color32,
+}
+
+impl Theme {
+    pub fn standard() -> Self {
+        Self {
+            players: [Color32::RED, Color32::BLUE],
+            empty_cell: Color32::LIGHT_GRAY,
+            grid: Stroke::new(1.0, Color32::DARK_GRAY),
+            edge_width: 4.0,
+            hover_width: 4.0,
+            bridge_width: 4.0,
+            candidate_text: Color32::from_black_alpha(120),
+            size_overlay: Stroke::new(1.0, Color32::from_white_alpha(80)),
+            oracle_win: Color32::GREEN,
+            oracle_loss: Color32::DARK_RED,
+            rejected_fill: Color32::from_black_alpha(120),
+            rejected_outline: Stroke::new(4.0, Color32::RED),
+            hint: Stroke::new(3.0, Color32::YELLOW),
+            drill_correct: Stroke::new(3.0, Color32::GREEN),
+            preview_width: 6.0,
+            winning_path: Color32::WHITE,
+            winning_cells: Stroke::new(3.0, Color32::GOLD),
+            last_move: Color32::WHITE,
+            last_move_size: 0.2,
+            last_move_width: 2.0,
+            coordinate_text: Color32::GRAY,
+            debug_text: Color32::GRAY,
+        }
+    }
+
+    /// Thick black lines, saturated stones on white cells and large outlines, for low vision.
+    pub fn high_contrast() -> Self {
+        Self {
+            players: [Color32::from_rgb(230, 0, 0), Color32::from_rgb(0, 60, 255)],
+            empty_cell: Color32::WHITE,
+            grid: Stroke::new(3.0, Color32::BLACK),
+            edge_width: 8.0,
+            hover_width: 8.0,
+            bridge_width: 6.0,
+            candidate_text: Color32::BLACK,
+            size_overlay: Stroke::new(3.0, Color32::BLACK),
+            oracle_win: Color32::from_rgb(0, 160, 0),
+            oracle_loss: Color32::BLACK,
+            rejected_fill: Color32::from_black_alpha(180),
+            rejected_outline: Stroke::new(8.0, Color32::from_rgb(255, 0, 255)),
+            hint: Stroke::new(8.0, Color32::BLACK),
+            drill_correct: Stroke::new(8.0, Color32::from_rgb(0, 160, 0)),
+            preview_width: 10.0,
+            winning_path: Color32::WHITE,
+            winning_cells: Stroke::new(6.0, Color32::from_rgb(255, 200, 0)),
+            last_move: Color32::WHITE,
+            last_move_size: 0.3,
+            last_move_width: 4.0,
+            coordinate_text: Color32::BLACK,
+            debug_text: Color32::BLACK,
+        }
+    }
+
+    pub fn player(&self, color: Color) -> Color32 {
+        self.players[board::color_index(color)]
+    }
+}
@@ -0,0 +1,22 @@
+//! Keeps the current game in the page URL's fragment (`#moves=5+c3+d5`), so it can be shared as a link.
+
+use eframe::wasm_bindgen::JsValue;
+
+const FRAGMENT_PREFIX: &str = "#moves=";
+
+/// The game from the page URL in the move-list notation, if there is one.
+pub fn read_shared_game() -> Option<String> {
+    let fragment = web_sys::window()?.location().hash().ok()?;
+    let encoded = fragment.strip_prefix(FRAGMENT_PREFIX)?;
+    Some(encoded.replace("%20", " ").replace('+', " "))
+}
+
+/// Replaces the page URL's fragment with `game`, without adding an entry to the browser history.
+pub fn write_shared_game(game: &str) {
+    let url = format!("{}{}", FRAGMENT_PREFIX, game.replace(' ', "+"));
+    if let Some(history) = web_sys::window().and_then(|window| window.history().ok()) {
+        history
+            .replace_state_with_url(&JsValue::NULL, "", Some(&url))
+            .ok();
+    }
+}
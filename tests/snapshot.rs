@@ -0,0 +1,72 @@
+use hex_ui::{
+    notation::board_to_ascii,
+    summary::{summary_to_json, GameResult, GameSummary, MoveSummary},
+    svg::{board_to_svg, SvgOptions},
+};
+use hexgame::{Color, Coords};
+use hexgame_ai::MctsHexGame;
+
+#[test]
+fn scripted_game_snapshot() {
+    let mut game = MctsHexGame::new(3, 0, 1);
+    for (x, y) in [(1, 1), (0, 0), (2, 0), (0, 2)] {
+        game.play(Coords::new(x, y))
+            .expect("scripted move is legal");
+    }
+
+    assert_eq!(board_to_ascii(&game.game.board), "B . R\n . R .\n  B . .\n");
+}
+
+#[test]
+fn svg_export_draws_every_cell() {
+    let mut game = MctsHexGame::new(3, 0, 1);
+    game.play(Coords::new(1, 1))
+        .expect("scripted move is legal");
+
+    let svg = board_to_svg(&game.game.board, &SvgOptions::default());
+    assert!(svg.starts_with("<svg "));
+    assert_eq!(svg.matches("<polygon ").count(), 9);
+    assert_eq!(svg.matches(r##"fill="#ff0000""##).count(), 1);
+}
+
+#[test]
+fn game_summary_json_snapshot() {
+    let summary = GameSummary {
+        size: 3,
+        players: ["Ann \"A\"".to_owned(), "Blue".to_owned()],
+        moves: vec![
+            MoveSummary {
+                coords: Coords::new(1, 1),
+                color: Color::Black,
+                eval: None,
+            },
+            MoveSummary {
+                coords: Coords::new(0, 2),
+                color: Color::White,
+                eval: Some(0.25),
+            },
+        ],
+        result: Some(GameResult {
+            winner: Color::Black,
+            resigned: true,
+        }),
+        started: Some(1_600_000_000),
+        finished: None,
+    };
+
+    assert_eq!(
+        summary_to_json(&summary),
+        r#"{
+  "size": 3,
+  "players": {"Red": "Ann \"A\"", "Blue": "Blue"},
+  "moves": [
+    {"cell": "b2", "player": "Red", "eval": null},
+    {"cell": "a3", "player": "Blue", "eval": 0.250}
+  ],
+  "result": {"winner": "Red", "resigned": true},
+  "started": 1600000000,
+  "finished": null
+}
+"#
+    );
+}